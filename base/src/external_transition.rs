@@ -47,7 +47,7 @@ impl JsonSerializationType<'_> for ExternalTransition {
 
 impl<CTX> Verifiable<CTX> for ExternalTransition
 where
-    CTX: Signer<SignedCommandPayload>,
+    CTX: Signer<SignedCommandPayload> + ?Sized,
 {
     // ExternalTransition is considered valid if:
     // - Its staged ledger diff is valid
@@ -0,0 +1,62 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Byte encoding of field elements
+//!
+//! A field element is encoded as the 32 little-endian bytes of its canonical
+//! representative, the same encoding as in bin_prot. The raw, hex and base58 forms of
+//! public keys and signatures are all built on these two functions so they agree on the
+//! byte order. Forms that are big-endian, such as the signature hex, reverse the result.
+
+use ark_ff::PrimeField;
+use proof_systems::o1_utils::FieldHelpers;
+
+/// Length in bytes of an encoded field element
+pub(crate) const FIELD_BYTES: usize = 32;
+
+/// Encode a field element as the little-endian bytes of its canonical representative
+pub(crate) fn fp_to_bytes_le<F: PrimeField>(f: &F) -> [u8; FIELD_BYTES] {
+    let mut bytes = [0; FIELD_BYTES];
+    bytes.copy_from_slice(&f.to_bytes());
+    bytes
+}
+
+/// Decode a field element from its little-endian bytes.
+/// Returns `None` unless given exactly [FIELD_BYTES] bytes encoding a value below the modulus
+pub(crate) fn fp_from_bytes_le<F: PrimeField>(bytes: &[u8]) -> Option<F> {
+    if bytes.len() != FIELD_BYTES {
+        return None;
+    }
+    F::from_bytes(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::{BigInteger, FpParameters};
+    use proof_systems::mina_hasher::Fp;
+
+    #[test]
+    fn fp_bytes_le_roundtrip() {
+        // x coordinate of B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV
+        let bytes = hex::decode("876fc2293ca688421553de93b14e8b25d08c7a9cb0d0ebc0ae19a78b3e1a4a1c")
+            .unwrap();
+        let x: Fp = fp_from_bytes_le(&bytes).unwrap();
+        assert_eq!(fp_to_bytes_le(&x).as_slice(), bytes.as_slice());
+
+        // The least significant byte comes first
+        let mut one = [0; FIELD_BYTES];
+        one[0] = 1;
+        assert_eq!(fp_to_bytes_le(&Fp::from(1u64)), one);
+        assert_eq!(fp_from_bytes_le::<Fp>(&one), Some(Fp::from(1u64)));
+    }
+
+    #[test]
+    fn fp_bytes_le_rejects_non_canonical() {
+        let modulus = <Fp as PrimeField>::Params::MODULUS.to_bytes_le();
+        assert_eq!(fp_from_bytes_le::<Fp>(&modulus), None);
+        assert_eq!(fp_from_bytes_le::<Fp>(&[0xff; FIELD_BYTES]), None);
+        assert_eq!(fp_from_bytes_le::<Fp>(&[0; FIELD_BYTES - 1]), None);
+        assert_eq!(fp_from_bytes_le::<Fp>(&[0; FIELD_BYTES + 1]), None);
+    }
+}
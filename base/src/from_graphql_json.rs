@@ -10,3 +10,9 @@ pub trait FromGraphQLJson {
     where
         Self: Sized;
 }
+
+/// Trait that serializes a struct into the json shape of the graphql API
+pub trait ToGraphQLJson {
+    /// Serialize to graphql json
+    fn to_graphql_json(&self) -> serde_json::Value;
+}
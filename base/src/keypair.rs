@@ -0,0 +1,206 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers for working with Mina keypairs
+
+use blake2::{
+    digest::{Update, VariableOutput},
+    Blake2bVar,
+};
+use proof_systems::mina_signer::Keypair;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Domain separation prefix so that seeds used here never collide with other blake2b uses
+const SEED_DOMAIN: &[u8] = b"MinaRsKeypairFromSeed";
+
+/// Base58check version byte of secret keys
+const SECRET_KEY_VERSION: u8 = 0x5a;
+/// Version byte of the serialized scalar that follows the base58check version byte
+const SCALAR_VERSION: u8 = 0x01;
+/// Secret key version byte, scalar version byte and little-endian scalar
+const SECRET_KEY_LEN: usize = 2 + 32;
+
+/// Error type for parsing a secret key
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum SecretKeyError {
+    /// The secret key is not valid base58check
+    #[error("Invalid base58: {0}")]
+    InvalidBase58(String),
+    /// The secret key has a version byte other than that of secret keys
+    #[error("Expected secret key version byte but got {0:#04x}")]
+    WrongVersion(u8),
+    /// The secret key does not decode to the expected number of bytes
+    #[error("Expected {} bytes but got {0}", SECRET_KEY_LEN)]
+    WrongLength(usize),
+    /// The secret key is not a valid scalar
+    #[error("Invalid secret key")]
+    InvalidKey,
+}
+
+/// Extension methods for [Keypair]
+pub trait KeypairExt: Sized {
+    /// Deterministically derive a keypair from a seed.
+    /// Intended for test fixtures and tooling, this is not a wallet key derivation scheme
+    fn from_seed(seed: &[u8; 32]) -> Self;
+
+    /// Parse a keypair from the big-endian hex of its secret key, the form used by the
+    /// reference signer tests
+    fn from_secret_hex(secret_hex: &str) -> Result<Self, SecretKeyError>;
+
+    /// Parse a keypair from the base58check form of its secret key exported by wallets,
+    /// starting with `EK`
+    fn from_secret_base58(secret_base58: &str) -> Result<Self, SecretKeyError>;
+
+    /// Base58check address of the public key, the sender address of commands it signs
+    fn address(&self) -> String;
+}
+
+impl KeypairExt for Keypair {
+    fn from_seed(seed: &[u8; 32]) -> Self {
+        // A zero scalar is not a valid secret key, so retry with a counter in the
+        // astronomically unlikely case the digest reduces to one
+        for counter in 0u8.. {
+            let mut hasher = Blake2bVar::new(32).expect("Invalid Blake2bVar output size");
+            hasher.update(SEED_DOMAIN);
+            hasher.update(seed);
+            hasher.update(&[counter]);
+            // The scalar is the secret key, so it and its hex are wiped on drop
+            let mut scalar = Zeroizing::new([0; 32]);
+            hasher
+                .finalize_variable(&mut scalar[..])
+                .expect("Output buffer matches the Blake2bVar output size");
+            // Clear the top two bits of the little-endian digest so that it is below 2^254,
+            // which is less than the order of the Pallas scalar field
+            scalar[31] &= 0x3f;
+            if scalar.iter().all(|&b| b == 0) {
+                continue;
+            }
+            // Secret key hex is big-endian
+            scalar.reverse();
+            let secret_hex = Zeroizing::new(hex::encode(&scalar[..]));
+            return Keypair::from_hex(&secret_hex)
+                .expect("A non-zero scalar below the field order is a valid secret key");
+        }
+        unreachable!("Blake2b digests of distinct inputs are not all zero")
+    }
+
+    fn from_secret_hex(secret_hex: &str) -> Result<Self, SecretKeyError> {
+        Keypair::from_hex(secret_hex).map_err(|_| SecretKeyError::InvalidKey)
+    }
+
+    fn from_secret_base58(secret_base58: &str) -> Result<Self, SecretKeyError> {
+        // The decoded bytes and their hex hold the secret key, so both are wiped on drop
+        let mut bytes = Zeroizing::new(
+            bs58::decode(secret_base58)
+                .with_check(None)
+                .into_vec()
+                .map_err(|e| SecretKeyError::InvalidBase58(e.to_string()))?,
+        );
+        match bytes.first() {
+            Some(&SECRET_KEY_VERSION) => {}
+            Some(&version) => return Err(SecretKeyError::WrongVersion(version)),
+            None => return Err(SecretKeyError::WrongLength(0)),
+        }
+        if bytes.len() != SECRET_KEY_LEN {
+            return Err(SecretKeyError::WrongLength(bytes.len()));
+        }
+        if bytes[1] != SCALAR_VERSION {
+            return Err(SecretKeyError::InvalidKey);
+        }
+        // Secret key hex is big-endian
+        let scalar = &mut bytes[2..];
+        scalar.reverse();
+        let secret_hex = Zeroizing::new(hex::encode(scalar));
+        Self::from_secret_hex(&secret_hex)
+    }
+
+    fn address(&self) -> String {
+        self.public.into_compressed().into_address()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::public_key::CompressedPubKeyExt;
+    use crate::user_commands::signed_command::builder::SignedTransferCommandBuilder;
+    use crate::user_commands::{SignedCommand, SignedCommandPayload};
+    use crate::verifiable::Verifiable;
+    use proof_systems::mina_signer::{self, NetworkId};
+
+    #[test]
+    fn keypair_from_seed_is_deterministic() {
+        let kp = Keypair::from_seed(&[7; 32]);
+        assert_eq!(kp.public, Keypair::from_seed(&[7; 32]).public);
+        assert_ne!(kp.public, Keypair::from_seed(&[8; 32]).public);
+        assert!(kp.public.into_compressed().is_valid());
+        // Pinned so that fixtures derived from seeds stay stable across releases
+        assert_eq!(
+            kp.public.into_address(),
+            "B62qj4t6dRppLGA9vPH8v4dBPSEvaFoYuCHDjKn1nsyhJuFGo1D7ibM"
+        );
+        assert_eq!(
+            Keypair::from_seed(&[0; 32]).public.into_address(),
+            "B62qostZpSdhQEVatYLfYKKBxq9YL4sZxqbodJVy1jiKCM6n2kqyn3j"
+        );
+    }
+
+    #[test]
+    fn keypair_address() {
+        // Secret key and source address of sign_payment_test_2
+        let kp =
+            Keypair::from_hex("3414fc16e86e6ac272fda03cf8dcb4d7d47af91b4b726494dab43bf773ce1779")
+                .expect("failed to create keypair");
+        assert_eq!(
+            kp.address(),
+            "B62qoG5Yk4iVxpyczUrBNpwtx2xunhL48dydN53A2VjoRwF8NUTbVr4"
+        );
+        assert_eq!(kp.address(), kp.public.into_address());
+    }
+
+    #[test]
+    fn keypair_from_secret_base58() -> Result<(), SecretKeyError> {
+        // Secret key of sign_payment_test_1
+        let hex = "164244176fddb5d769b7de2027469d027ad428fadcc0c02396e6280142efb718";
+        let base58 = "EKDt66ubGg5SDiwcQABWfFZaruq6idcyrLLfyZQjoH4CN3PHEiNj";
+        let kp = Keypair::from_secret_base58(base58)?;
+        assert_eq!(kp.public, Keypair::from_secret_hex(hex)?.public);
+        assert_eq!(
+            kp.address(),
+            "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV"
+        );
+
+        assert!(matches!(
+            Keypair::from_secret_base58("EKDt66ubGg5SDiwcQABWfFZaruq6idcyrLLfyZQjoH4CN3PHEiNk"),
+            Err(SecretKeyError::InvalidBase58(_))
+        ));
+        // A public key address is not a secret key
+        assert_eq!(
+            Keypair::from_secret_base58("B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV")
+                .map(|kp| kp.public),
+            Err(SecretKeyError::WrongVersion(0xcb))
+        );
+        assert_eq!(
+            Keypair::from_secret_hex("zz").map(|kp| kp.public),
+            Err(SecretKeyError::InvalidKey)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn keypair_from_seed_signs() {
+        let kp = Keypair::from_seed(&[0; 32]);
+        let payload = SignedTransferCommandBuilder::new(
+            kp.public.into_compressed(),
+            Keypair::from_seed(&[1; 32]).public.into_compressed(),
+            1000000000,
+            10000000,
+            0,
+        )
+        .build();
+        let cmd = SignedCommand::from_payload(payload, kp, NetworkId::TESTNET);
+        let mut ctx = mina_signer::create_legacy::<SignedCommandPayload>(NetworkId::TESTNET);
+        assert!(cmd.verify(&mut ctx));
+    }
+}
@@ -19,13 +19,18 @@ mod constants;
 pub mod delta_transition_chain_proof;
 pub mod epoch_data;
 pub mod external_transition;
+mod field_bytes;
 pub mod from_graphql_json;
 pub mod global_slot;
+pub mod keypair;
+pub mod network_constants;
 pub mod numbers;
 pub mod protocol_state;
 pub mod protocol_state_proof;
 pub mod protocol_version;
+pub mod public_key;
 mod serialization_type_conversions;
+pub mod signature;
 pub mod snark_work;
 pub mod staged_ledger_diff;
 pub mod user_commands;
@@ -53,10 +58,14 @@ pub mod types {
     pub use super::epoch_data::*;
     pub use super::external_transition::*;
     pub use super::global_slot::*;
+    pub use super::keypair::*;
+    pub use super::network_constants::*;
     pub use super::numbers::*;
     pub use super::protocol_state::*;
     pub use super::protocol_state_proof::*;
     pub use super::protocol_version::*;
+    pub use super::public_key::*;
+    pub use super::signature::*;
     pub use super::snark_work::*;
     pub use super::staged_ledger_diff::*;
     pub use super::user_commands::*;
@@ -0,0 +1,161 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Consensus timing constants of the networks commands can be signed for, and their names
+
+use crate::numbers::{BlockTime, Length};
+use proof_systems::mina_signer::NetworkId;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Timing constants of a network, needed to relate slots to epochs and wall-clock time
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct NetworkConstants {
+    /// Number of slots per epoch
+    pub slots_per_epoch: Length,
+    /// Duration of a slot in milliseconds
+    pub slot_duration_ms: u64,
+    /// Timestamp of the genesis block, at the start of slot 0
+    pub genesis_timestamp: BlockTime,
+}
+
+impl NetworkConstants {
+    /// Constants of the mainnet, launched on 2021-03-17
+    pub const MAINNET: Self = Self {
+        slots_per_epoch: Length(7140),
+        slot_duration_ms: 180000,
+        genesis_timestamp: BlockTime::from_unix_epoch_millis(1615939200000),
+    };
+
+    /// Constants of the testnet, matching the defaults of
+    /// [ProtocolConstants](crate::protocol_state::ProtocolConstants)
+    pub const TESTNET: Self = Self {
+        slots_per_epoch: Length(7140),
+        slot_duration_ms: 180000,
+        genesis_timestamp: BlockTime::from_unix_epoch_millis(1655755201000),
+    };
+
+    /// Constants of the given network
+    pub const fn for_network(network: NetworkId) -> Self {
+        match network {
+            NetworkId::MAINNET => Self::MAINNET,
+            NetworkId::TESTNET => Self::TESTNET,
+        }
+    }
+}
+
+/// Canonical name of the network, as accepted by [NetworkName]
+pub fn network_name(network: &NetworkId) -> &'static str {
+    match network {
+        NetworkId::MAINNET => "mainnet",
+        NetworkId::TESTNET => "testnet",
+    }
+}
+
+/// A [NetworkId] parsed from or displayed as its name, e.g. for command line arguments.
+/// The foreign [NetworkId] cannot implement [FromStr] itself.
+///
+/// Names are case insensitive. Besides the canonical "mainnet" and "testnet", "main" is
+/// accepted for the mainnet, and "test" and "devnet" for the testnet, as devnet commands
+/// are signed for the testnet
+#[derive(Clone, Debug)]
+pub struct NetworkName(pub NetworkId);
+
+impl FromStr for NetworkName {
+    type Err = UnknownNetworkError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "mainnet" | "main" => Ok(Self(NetworkId::MAINNET)),
+            "testnet" | "test" | "devnet" => Ok(Self(NetworkId::TESTNET)),
+            _ => Err(UnknownNetworkError(s.into())),
+        }
+    }
+}
+
+impl TryFrom<&str> for NetworkName {
+    type Error = UnknownNetworkError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for NetworkName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(network_name(&self.0))
+    }
+}
+
+impl From<NetworkId> for NetworkName {
+    fn from(network: NetworkId) -> Self {
+        Self(network)
+    }
+}
+
+impl From<NetworkName> for NetworkId {
+    fn from(name: NetworkName) -> Self {
+        name.0
+    }
+}
+
+/// Error returned when parsing a [NetworkName] that is not known
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("Unknown network {0}, expected mainnet or testnet")]
+pub struct UnknownNetworkError(pub String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol_state::ProtocolConstants;
+
+    #[test]
+    fn mainnet_constants() {
+        let constants = NetworkConstants::for_network(NetworkId::MAINNET);
+        assert_eq!(constants, NetworkConstants::MAINNET);
+        assert_eq!(constants.slots_per_epoch, Length(7140));
+        // 3 minute slots
+        assert_eq!(constants.slot_duration_ms, 3 * 60 * 1000);
+        assert_eq!(
+            constants.genesis_timestamp.datetime(),
+            time::macros::datetime!(2021-03-17 00:00 UTC)
+        );
+    }
+
+    #[test]
+    fn testnet_constants_match_protocol_defaults() {
+        let constants = NetworkConstants::for_network(NetworkId::TESTNET);
+        let defaults = ProtocolConstants::default();
+        assert_eq!(constants.slots_per_epoch, defaults.slots_per_epoch);
+        assert_eq!(
+            constants.genesis_timestamp,
+            defaults.genesis_state_timestamp
+        );
+    }
+
+    #[test]
+    fn network_names() {
+        for network in [NetworkId::MAINNET, NetworkId::TESTNET] {
+            let name = NetworkName(network.clone()).to_string();
+            assert_eq!(name, network_name(&network));
+            let parsed: NetworkId = name.parse::<NetworkName>().unwrap().into();
+            assert_eq!(
+                NetworkConstants::for_network(parsed),
+                NetworkConstants::for_network(network)
+            );
+        }
+        assert!(matches!(
+            NetworkName::try_from("Devnet"),
+            Ok(NetworkName(NetworkId::TESTNET))
+        ));
+        assert!(matches!(
+            "MAIN".parse::<NetworkName>(),
+            Ok(NetworkName(NetworkId::MAINNET))
+        ));
+        assert_eq!(
+            NetworkName::from_str("berkeley").unwrap_err(),
+            UnknownNetworkError("berkeley".into())
+        );
+    }
+}
@@ -3,6 +3,7 @@
 
 //! Newtypes for different numeric types used throughout Mina
 
+use crate::network_constants::NetworkConstants;
 use crate::{constants::*, *};
 use derive_more::{From, Into};
 use mina_crypto::prelude::*;
@@ -10,14 +11,22 @@ use mina_hasher::{Hashable, ROInput};
 use mina_serialization_types::{json::*, *};
 use mina_serialization_types_macros::*;
 use num::Integer;
+use proof_systems::mina_signer::NetworkId;
 use std::fmt;
 use thiserror::Error;
 use time::Duration;
 
-#[derive(Clone, Default, Eq, PartialEq, Debug, From, Into)]
+#[derive(Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, From, Into)]
 /// Newtype for TokenIds
 pub struct TokenId(pub u64);
 
+impl TokenId {
+    /// The default token, MINA. Distinct from `TokenId::default()` which is the invalid id 0
+    pub const fn default_token() -> Self {
+        Self(1)
+    }
+}
+
 impl Hashable for TokenId {
     type D = ();
 
@@ -94,9 +103,26 @@ impl ToChunkedROInput for SignedAmount {
 /// let amount = Amount(1000000030);
 /// assert_eq!(amount.to_string(), "1.000000030");
 /// ```
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, From, Into)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, From, Into)]
 pub struct Amount(pub u64);
 
+impl Amount {
+    /// Practical upper bound of the total supply, 1 billion MINA. No account can hold more,
+    /// so larger amounts are almost certainly a unit mistake
+    pub const MAX_SUPPLY: Self = Self(1_000_000_000 * MINA_PRECISION);
+
+    /// If the amount is at most [Amount::MAX_SUPPLY]
+    pub fn within_supply(&self) -> bool {
+        *self <= Self::MAX_SUPPLY
+    }
+
+    /// Multiply the amount by a count, e.g. the total of an airdrop of this amount to
+    /// `factor` accounts. None if the result overflows
+    pub fn checked_mul_u64(&self, factor: u64) -> Option<Self> {
+        self.0.checked_mul(factor).map(Self)
+    }
+}
+
 impl fmt::Display for Amount {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let (q, r) = self.0.div_rem(&MINA_PRECISION);
@@ -154,9 +180,18 @@ impl std::str::FromStr for Amount {
 }
 
 /// Number representing how many txns sent from an account
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, From, Into)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, From, Into)]
 pub struct AccountNonce(pub u32);
 
+impl TryFrom<u64> for AccountNonce {
+    type Error = std::num::TryFromIntError;
+
+    /// Rejects nonces that do not fit the u32 hashed into signatures instead of truncating them
+    fn try_from(nonce: u64) -> Result<Self, Self::Error> {
+        u32::try_from(nonce).map(Self)
+    }
+}
+
 impl Hashable for AccountNonce {
     type D = ();
 
@@ -176,12 +211,54 @@ impl ToChunkedROInput for AccountNonce {
 }
 
 /// Consensus slot index
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, From, Into)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, From, Into)]
 pub struct GlobalSlotNumber(pub u32);
 
+impl TryFrom<u64> for GlobalSlotNumber {
+    type Error = std::num::TryFromIntError;
+
+    /// Rejects slots that do not fit the u32 hashed into signatures instead of truncating them
+    fn try_from(slot: u64) -> Result<Self, Self::Error> {
+        u32::try_from(slot).map(Self)
+    }
+}
+
 impl GlobalSlotNumber {
     /// Maximum value this type can hold
     pub const MAX: Self = Self(u32::MAX);
+
+    /// Sentinel slot used for `valid_until` to denote a command that never expires
+    pub const fn forever() -> Self {
+        Self::MAX
+    }
+
+    /// If this is the never-expiring sentinel slot
+    pub const fn is_forever(&self) -> bool {
+        self.0 == Self::MAX.0
+    }
+
+    /// Unix timestamp in seconds of the start of this slot on the given network.
+    /// The never-expiring sentinel slot maps to `i64::MAX`, later than any real slot
+    pub fn to_unix_timestamp(&self, network: NetworkId) -> i64 {
+        if self.is_forever() {
+            return i64::MAX;
+        }
+        let constants = NetworkConstants::for_network(network);
+        let millis =
+            constants.genesis_timestamp.epoch_millis() + self.0 as u64 * constants.slot_duration_ms;
+        (millis / 1000) as i64
+    }
+
+    /// The slot containing the given unix timestamp in seconds on the given network, the
+    /// inverse of [GlobalSlotNumber::to_unix_timestamp]. Timestamps before genesis give
+    /// slot 0, and slots past the range saturate below the never-expiring sentinel
+    pub fn from_unix_timestamp(ts: i64, network: NetworkId) -> Self {
+        let constants = NetworkConstants::for_network(network);
+        let genesis_millis = constants.genesis_timestamp.epoch_millis() as i128;
+        let since_genesis = (ts as i128 * 1000 - genesis_millis).max(0);
+        let slot = since_genesis / constants.slot_duration_ms as i128;
+        Self(slot.min(Self::MAX.0 as i128 - 1) as u32)
+    }
 }
 
 impl ToChunkedROInput for GlobalSlotNumber {
@@ -11,7 +11,9 @@ use crate::{
     numbers::{BlockTime, Length},
     *,
 };
-use mina_crypto::hash::StateHash;
+/// Re-exported as the hash identifying protocol states, with base58check conversions
+/// through `FromStr` and `Display`
+pub use mina_crypto::hash::StateHash;
 use mina_serialization_types::{json::*, v1::*};
 use mina_serialization_types_macros::AutoFrom;
 use proof_systems::{
@@ -94,15 +96,17 @@ pub struct ProtocolStateLegacy {
 
 impl_from_with_proxy!(ProtocolStateLegacy, ProtocolStateV1, ProtocolStateJson);
 
+impl BinProtSerializationType<'_> for ProtocolStateLegacy {
+    type T = ProtocolStateV1;
+}
+
 impl Hashable for ProtocolStateLegacy {
     type D = ();
 
     fn to_roinput(&self) -> ROInput {
-        let mut hasher = create_legacy(());
-        let body_hash = hasher.hash(&self.body);
         ROInput::new()
             .append_hashable(&self.previous_state_hash)
-            .append_field(body_hash)
+            .append_field(self.body.body_hash())
     }
 
     fn domain_string(_: Self::D) -> Option<String> {
@@ -116,7 +120,10 @@ impl ProtocolStateLegacy {
         &self.body.consensus_state.curr_global_slot
     }
 
-    /// Calculates the state hash field of current protocol state
+    /// Calculates the state hash field of current protocol state.
+    /// This is the hash of the previous state hash followed by the
+    /// [body hash](ProtocolStateBodyLegacy::body_hash), so states commit to the chain of
+    /// bodies before them
     pub fn state_hash_fp(&self) -> Fp {
         let mut hasher = create_legacy(());
         hasher.hash(self)
@@ -149,6 +156,14 @@ impl_from_with_proxy!(
     ProtocolStateBodyJson
 );
 
+impl ProtocolStateBodyLegacy {
+    /// Calculates the body hash, the commitment to the body the state hash is derived from
+    pub fn body_hash(&self) -> Fp {
+        let mut hasher = create_legacy(());
+        hasher.hash(self)
+    }
+}
+
 impl Hashable for ProtocolStateBodyLegacy {
     type D = ();
 
@@ -247,6 +262,14 @@ impl Hashable for ProtocolStateBody {
     }
 }
 
+impl ProtocolStateBody {
+    /// Calculates the body hash, the commitment to the body the state hash is derived from
+    pub fn body_hash(&self) -> Fp {
+        let mut hasher = create_kimchi(());
+        hasher.hash(self)
+    }
+}
+
 impl ToChunkedROInput for ProtocolStateBody {
     fn to_chunked_roinput(&self) -> ChunkedROInput {
         ChunkedROInput::new()
@@ -317,16 +340,17 @@ impl Hashable for ProtocolState {
 
 impl ToChunkedROInput for ProtocolState {
     fn to_chunked_roinput(&self) -> ChunkedROInput {
-        let mut hasher = create_kimchi(());
-        let body_hash = hasher.hash(&self.body);
         ChunkedROInput::new()
             .append_chunked(&self.previous_state_hash)
-            .append_field(body_hash)
+            .append_field(self.body.body_hash())
     }
 }
 
 impl ProtocolState {
-    /// Calculates the state hash field of current protocol state
+    /// Calculates the state hash field of current protocol state.
+    /// This is the hash of the previous state hash followed by the
+    /// [body hash](ProtocolStateBody::body_hash), so states commit to the chain of
+    /// bodies before them
     pub fn state_hash_fp(&self) -> Fp {
         let mut hasher = create_kimchi(());
         hasher.hash(self)
@@ -337,11 +361,71 @@ impl ProtocolState {
         let f = self.state_hash_fp();
         (&f).into()
     }
+
+    /// Order the chains ending at two tips, the preferred one being greater.
+    /// This is the tiebreak Mina applies to short range forks: the longer chain wins, then
+    /// the greater blake2b digest of the last VRF output, then the greater state hash.
+    ///
+    /// Long range forks are decided by the minimum window density first, which needs more
+    /// than the two tips, see `ChainSelection` in the consensus crate. Use this directly
+    /// only when the candidates are known to share a recent lock checkpoint
+    pub fn compare_chains(&self, other: &ProtocolState) -> std::cmp::Ordering {
+        let (a, b) = (&self.body.consensus_state, &other.body.consensus_state);
+        a.blockchain_length
+            .0
+            .cmp(&b.blockchain_length.0)
+            .then_with(|| a.last_vrf_output.digest().cmp(&b.last_vrf_output.digest()))
+            .then_with(|| self.state_hash_fp().cmp(&other.state_hash_fp()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::consensus_state::VrfOutputTruncated;
+    use std::cmp::Ordering;
+
+    fn state_with(length: u32, vrf_output: &[u8], previous_state_hash: [u8; 32]) -> ProtocolState {
+        let mut state = ProtocolState {
+            previous_state_hash: previous_state_hash.into(),
+            ..Default::default()
+        };
+        state.body.consensus_state.blockchain_length = Length(length);
+        state.body.consensus_state.last_vrf_output = VrfOutputTruncated(vrf_output.to_vec());
+        state
+    }
+
+    #[test]
+    fn compare_chains_prefers_longer() {
+        // Length decides before the VRF output and the hash
+        let short = state_with(10, &[0xff; 32], [1; 32]);
+        let long = state_with(11, &[0; 32], [2; 32]);
+        assert_eq!(short.compare_chains(&long), Ordering::Less);
+        assert_eq!(long.compare_chains(&short), Ordering::Greater);
+    }
+
+    #[test]
+    fn compare_chains_tiebreaks() {
+        let a = state_with(10, &[1; 32], [1; 32]);
+        let b = state_with(10, &[2; 32], [1; 32]);
+        let expected = a
+            .body
+            .consensus_state
+            .last_vrf_output
+            .digest()
+            .cmp(&b.body.consensus_state.last_vrf_output.digest());
+        assert_ne!(expected, Ordering::Equal);
+        assert_eq!(a.compare_chains(&b), expected);
+        assert_eq!(b.compare_chains(&a), expected.reverse());
+
+        // Same length and VRF output, the state hash decides
+        let c = state_with(10, &[1; 32], [2; 32]);
+        let expected = a.state_hash_fp().cmp(&c.state_hash_fp());
+        assert_ne!(expected, Ordering::Equal);
+        assert_eq!(a.compare_chains(&c), expected);
+        assert_eq!(c.compare_chains(&a), expected.reverse());
+        assert_eq!(a.compare_chains(&a.clone()), Ordering::Equal);
+    }
 
     #[test]
     fn protocol_state_from_graphql_json() -> anyhow::Result<()> {
@@ -7,6 +7,7 @@ use mina_serialization_types_macros::AutoFrom;
 
 #[derive(Clone, Debug, Eq, PartialEq, AutoFrom)]
 #[auto_from(mina_serialization_types::protocol_version::ProtocolVersion)]
+#[auto_from(mina_serialization_types::protocol_version::ProtocolVersionStringJson)]
 /// Defines a version of the Mina protocol in semver format
 pub struct ProtocolVersion {
     /// Major version number
@@ -26,3 +27,42 @@ impl Default for ProtocolVersion {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mina_serialization_types::json::{ProtocolVersionJson, ProtocolVersionStringJson};
+
+    #[test]
+    fn protocol_version_json_forms() -> anyhow::Result<()> {
+        let expected = ProtocolVersion {
+            major: 2,
+            minor: 1,
+            patch: 3,
+        };
+        let object = serde_json::json!({ "major": 2, "minor": 1, "patch": 3 });
+        let string = serde_json::json!("2.1.3");
+
+        let from_object: ProtocolVersionJson = serde_json::from_value(object.clone())?;
+        assert_eq!(ProtocolVersion::from(from_object), expected);
+        for json in [&string, &object] {
+            let v: ProtocolVersionStringJson = serde_json::from_value(json.clone())?;
+            assert_eq!(ProtocolVersion::from(v), expected);
+        }
+
+        // Each form serializes in its own shape
+        assert_eq!(
+            serde_json::to_value(ProtocolVersionJson::from(expected.clone()))?,
+            object
+        );
+        assert_eq!(
+            serde_json::to_value(ProtocolVersionStringJson::from(expected))?,
+            string
+        );
+
+        for malformed in ["2.1", "2.1.3.4", "2.x.3"] {
+            assert!(serde_json::from_value::<ProtocolVersionStringJson>(malformed.into()).is_err());
+        }
+        Ok(())
+    }
+}
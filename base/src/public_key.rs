@@ -0,0 +1,479 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers for working with Mina public keys
+//!
+//! A [CompressedPubKey] is the x coordinate of a point on the Pallas curve together with
+//! the parity of its y coordinate. For a valid key exactly one of the two points with that
+//! x coordinate has the given parity, so two valid compressed keys compare equal if and
+//! only if they represent the same point, and `from_address(into_address(pk)) == pk`.
+//! The identity has no affine coordinates and compresses to `x = 0`, which is never the
+//! public key of a Mina keypair, so it is rejected explicitly.
+//!
+//! Addresses parsed with [CompressedPubKeyExt::from_address_checked], by
+//! `SignedTransferCommandBuilder::from_addresses` or from graphql json are rejected when
+//! they are the identity or not on the curve. The conversions from the bin_prot and json
+//! types of `mina-serialization-types` are infallible and do not check keys, nor does
+//! `CompressedPubKey::from_address` from `mina-signer`: call [CompressedPubKeyExt::is_valid]
+//! on keys decoded that way before relying on them.
+//!
+//! Both key types are foreign, so instead of `From` and `TryFrom` impls a [PubKey] is
+//! compressed with `PubKey::into_compressed` and decompressed with
+//! [CompressedPubKeyExt::to_pub_key].
+
+use crate::field_bytes::{fp_from_bytes_le, fp_to_bytes_le, FIELD_BYTES};
+use ark_ff::{field_new, BigInteger, Field, PrimeField, SquareRootField, Zero};
+use proof_systems::mina_hasher::Fp;
+use proof_systems::mina_signer::{CompressedPubKey, CurvePoint, PubKey};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use thiserror::Error;
+
+/// Coefficient b of the Pallas curve equation y^2 = x^3 + b
+const PALLAS_B: Fp = field_new!(Fp, "5");
+
+/// Base58check version byte of public key addresses
+const ADDRESS_VERSION: u8 = 0xcb;
+/// Version bytes of the serialized compressed point that follow the address version byte
+const KEY_VERSIONS: [u8; 2] = [0x01, 0x01];
+/// Address version byte, key version bytes, x coordinate and parity byte
+const ADDRESS_LEN: usize = 1 + KEY_VERSIONS.len() + FIELD_BYTES + 1;
+
+/// Error type for converting a [CompressedPubKey] from or to its base58check address
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum AddressFormatError {
+    /// The address is not valid base58
+    #[error("Invalid base58: {0}")]
+    InvalidBase58(String),
+    /// The trailing checksum does not match the address
+    #[error("Bad address checksum")]
+    BadChecksum,
+    /// The leading version byte is not the one of public key addresses
+    #[error(
+        "Wrong address version byte {version:#04x}, expected {expected:#04x}",
+        version = .0,
+        expected = ADDRESS_VERSION
+    )]
+    WrongVersion(u8),
+    /// The address decodes to this many bytes instead of a whole compressed key
+    #[error(
+        "Wrong address length of {len} bytes, expected {expected}",
+        len = .0,
+        expected = ADDRESS_LEN
+    )]
+    WrongLength(usize),
+    /// The decoded bytes are not a canonical serialized compressed point
+    #[error("Malformed public key bytes")]
+    MalformedKey,
+    /// The key is not a point on the curve other than the identity
+    #[error("Public key is not a valid curve point")]
+    InvalidKey,
+}
+
+/// Error type for building a [CompressedPubKey] from the hex of its x coordinate
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum PubKeyHexError {
+    /// The input is not valid hex
+    #[error("Invalid hex: {0}")]
+    InvalidHex(#[from] hex::FromHexError),
+    /// The input does not decode to exactly one field element
+    #[error("Expected {expected} bytes but got {len}", len = .0, expected = FIELD_BYTES)]
+    InvalidLength(usize),
+    /// x is not a canonical base field element, i.e. it is not below the modulus
+    #[error("x is not a valid base field element")]
+    NonCanonical,
+}
+
+/// Error returned when decompressing a [CompressedPubKey] that is not a valid key
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[error("Compressed public key is not a valid curve point")]
+pub struct InvalidPubKeyError;
+
+/// Extension methods for [CompressedPubKey]
+pub trait CompressedPubKeyExt {
+    /// Whether this is the compressed form of a point on the curve other than the identity
+    fn is_valid(&self) -> bool;
+
+    /// Decompress into the curve point, failing if this is not a valid key
+    fn to_pub_key(&self) -> Result<PubKey, InvalidPubKeyError>;
+
+    /// Parse a base58check address, rejecting anything that is not a valid key
+    fn from_address_checked(address: &str) -> Result<Self, AddressFormatError>
+    where
+        Self: Sized;
+
+    /// Encode as a base58check address, failing for keys that are not valid
+    fn to_address_checked(&self) -> Result<String, AddressFormatError>;
+
+    /// The x coordinate as little-endian hex, the byte order it is serialized in
+    fn x_to_hex(&self) -> String;
+
+    /// Build a key from the little-endian hex of its x coordinate and the parity of y.
+    /// Only the encoding of x is checked, use [CompressedPubKeyExt::is_valid] to check
+    /// that the result is a point on the curve
+    fn from_x_and_parity(x_hex: &str, is_odd: bool) -> Result<Self, PubKeyHexError>
+    where
+        Self: Sized;
+
+    /// Feed the key into a hasher, consistently with its `Eq`.
+    /// Lets types holding keys implement [Hash] as the foreign key type does not
+    fn hash_key<H: Hasher>(&self, state: &mut H);
+}
+
+impl CompressedPubKeyExt for CompressedPubKey {
+    fn is_valid(&self) -> bool {
+        if self.x.is_zero() {
+            return false;
+        }
+        let y_squared = self.x.square() * self.x + PALLAS_B;
+        y_squared.legendre().is_qr()
+    }
+
+    fn to_pub_key(&self) -> Result<PubKey, InvalidPubKeyError> {
+        if self.x.is_zero() {
+            return Err(InvalidPubKeyError);
+        }
+        let y_squared = self.x.square() * self.x + PALLAS_B;
+        let y = y_squared.sqrt().ok_or(InvalidPubKeyError)?;
+        // The parity is that of the canonical integer, read from its limbs without
+        // serializing to bytes
+        let y = if y.into_repr().is_odd() == self.is_odd {
+            y
+        } else {
+            -y
+        };
+        Ok(PubKey::from_point_unsafe(CurvePoint::new(self.x, y, false)))
+    }
+
+    fn from_address_checked(address: &str) -> Result<Self, AddressFormatError> {
+        let bytes = bs58::decode(address)
+            .with_check(None)
+            .into_vec()
+            .map_err(|e| match e {
+                bs58::decode::Error::InvalidChecksum { .. } | bs58::decode::Error::NoChecksum => {
+                    AddressFormatError::BadChecksum
+                }
+                e => AddressFormatError::InvalidBase58(e.to_string()),
+            })?;
+        match bytes.first() {
+            Some(&ADDRESS_VERSION) => {}
+            Some(&version) => return Err(AddressFormatError::WrongVersion(version)),
+            None => return Err(AddressFormatError::WrongLength(0)),
+        }
+        if bytes.len() != ADDRESS_LEN {
+            return Err(AddressFormatError::WrongLength(bytes.len()));
+        }
+        if bytes[1..3] != KEY_VERSIONS {
+            return Err(AddressFormatError::MalformedKey);
+        }
+        let x =
+            fp_from_bytes_le(&bytes[3..3 + FIELD_BYTES]).ok_or(AddressFormatError::MalformedKey)?;
+        let is_odd = match bytes[35] {
+            0 => false,
+            1 => true,
+            _ => return Err(AddressFormatError::MalformedKey),
+        };
+        let pk = CompressedPubKey { x, is_odd };
+        if !pk.is_valid() {
+            return Err(AddressFormatError::InvalidKey);
+        }
+        Ok(pk)
+    }
+
+    fn to_address_checked(&self) -> Result<String, AddressFormatError> {
+        if !self.is_valid() {
+            return Err(AddressFormatError::InvalidKey);
+        }
+        Ok(self.into_address())
+    }
+
+    fn x_to_hex(&self) -> String {
+        hex::encode(fp_to_bytes_le(&self.x))
+    }
+
+    fn from_x_and_parity(x_hex: &str, is_odd: bool) -> Result<Self, PubKeyHexError> {
+        let bytes = hex::decode(x_hex)?;
+        if bytes.len() != FIELD_BYTES {
+            return Err(PubKeyHexError::InvalidLength(bytes.len()));
+        }
+        let x = fp_from_bytes_le(&bytes).ok_or(PubKeyHexError::NonCanonical)?;
+        Ok(Self { x, is_odd })
+    }
+
+    fn hash_key<H: Hasher>(&self, state: &mut H) {
+        self.x.hash(state);
+        self.is_odd.hash(state);
+    }
+}
+
+/// A [CompressedPubKey] ordered by its x coordinate and then the parity of y, to key
+/// ordered maps as the foreign key type is not `Ord`. Orders equal exactly when the
+/// keys are equal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrdPubKey(pub CompressedPubKey);
+
+impl Ord for OrdPubKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.0.x.into_repr(), self.0.is_odd).cmp(&(other.0.x.into_repr(), other.0.is_odd))
+    }
+}
+
+impl PartialOrd for OrdPubKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for OrdPubKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash_key(state)
+    }
+}
+
+impl From<CompressedPubKey> for OrdPubKey {
+    fn from(pk: CompressedPubKey) -> Self {
+        Self(pk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proof_systems::mina_signer::Keypair;
+
+    const ADDRESSES: [&str; 7] = [
+        "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+        "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+        "B62qoG5Yk4iVxpyczUrBNpwtx2xunhL48dydN53A2VjoRwF8NUTbVr4",
+        "B62qrKG4Z8hnzZqp1AL8WsQhQYah3quN1qUj3SyfJA8Lw135qWWg1mi",
+        "B62qoqiAgERjCjXhofXiD7cMLJSKD8hE8ZtMh4jX5MPNgKB4CFxxm1N",
+        "B62qkPv5qdMBHRcZtPXJEegRvEE9aLwNsyBx4ZZAQ94XKixJNdD26aY",
+        "B62qp5MgMnCrd2bB8pGpPVmAntym3Qfx3vu7wBWwJ5p9e6eU9srYx9v",
+    ];
+
+    #[test]
+    fn compressed_pubkey_address_roundtrip() {
+        for address in ADDRESSES {
+            let pk = CompressedPubKey::from_address(address).unwrap();
+            assert!(pk.is_valid());
+            assert_eq!(pk.into_address(), address);
+            assert_eq!(
+                CompressedPubKey::from_address(&pk.into_address()).unwrap(),
+                pk
+            );
+        }
+    }
+
+    #[test]
+    fn compressed_pubkey_parity_distinguishes_points() {
+        for address in ADDRESSES {
+            let pk = CompressedPubKey::from_address(address).unwrap();
+            // Same x with the other parity is the negated point, a different valid key
+            let negated = CompressedPubKey {
+                x: pk.x,
+                is_odd: !pk.is_odd,
+            };
+            assert!(negated.is_valid());
+            assert_ne!(negated, pk);
+            assert_ne!(negated.into_address(), address);
+            assert_eq!(
+                CompressedPubKey::from_address(&negated.into_address()).unwrap(),
+                negated
+            );
+        }
+    }
+
+    #[test]
+    fn compressed_pubkey_identity_is_rejected() {
+        assert!(!CompressedPubKey::empty().is_valid());
+        assert!(!CompressedPubKey {
+            x: Fp::zero(),
+            is_odd: true,
+        }
+        .is_valid());
+    }
+
+    #[test]
+    fn compressed_pubkey_address_checked_roundtrip() -> Result<(), AddressFormatError> {
+        for address in ADDRESSES {
+            let pk = CompressedPubKey::from_address_checked(address)?;
+            assert_eq!(pk, CompressedPubKey::from_address(address).unwrap());
+            assert_eq!(pk.to_address_checked()?, address);
+        }
+        assert_eq!(
+            CompressedPubKey::empty().to_address_checked(),
+            Err(AddressFormatError::InvalidKey)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compressed_pubkey_address_checked_errors() {
+        // Each address is the first of ADDRESSES with the described tampering
+        let cases = [
+            // last checksum byte flipped
+            (
+                "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzW",
+                AddressFormatError::BadChecksum,
+            ),
+            // parity byte dropped
+            (
+                "3HYgbtZkDnQ4fFWTgfPWtDBcA6MeDMjtwrhjGkXKBU18C3MeWDwzjc",
+                AddressFormatError::WrongLength(35),
+            ),
+            // trailing zero byte appended
+            (
+                "mXD8N1DQo4bChy1xqtB1eepoDiinbCDJ6BZ5kwrb7eMZ5c95qqw7PSAK",
+                AddressFormatError::WrongLength(37),
+            ),
+            // version byte 0xcc
+            (
+                "B8uzRC9cJVbooXbsg2HncaoJP7v6XsFs1VRKjJsrvneJSzj3txiGWpV",
+                AddressFormatError::WrongVersion(0xcc),
+            ),
+            // parity byte 2
+            (
+                "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8ShfEWJG",
+                AddressFormatError::MalformedKey,
+            ),
+            // x coordinate of the identity
+            (
+                "B62qiTKpEPjGTSHZrtM8uXiKgn8So916pLmNJKDhKeyBQL9TDb3nvBG",
+                AddressFormatError::InvalidKey,
+            ),
+        ];
+        for (address, expected) in cases {
+            assert_eq!(
+                CompressedPubKey::from_address_checked(address),
+                Err(expected),
+                "{address}"
+            );
+        }
+        assert!(matches!(
+            CompressedPubKey::from_address_checked("B62q0"),
+            Err(AddressFormatError::InvalidBase58(_))
+        ));
+    }
+
+    #[test]
+    fn compressed_pubkey_off_curve_is_rejected() {
+        // 2^3 + 5 is not a square in the base field
+        let pk = CompressedPubKey {
+            x: Fp::from(2u64),
+            is_odd: false,
+        };
+        assert!(!pk.is_valid());
+    }
+
+    #[test]
+    fn compressed_pubkey_decompress_roundtrip() {
+        for address in ADDRESSES {
+            let compressed = CompressedPubKey::from_address(address).unwrap();
+            let pk = compressed.to_pub_key().unwrap();
+            assert_eq!(pk, PubKey::from_address(address).unwrap());
+            assert_eq!(pk.into_compressed(), compressed);
+
+            // The other point with the same x coordinate
+            let flipped = CompressedPubKey {
+                is_odd: !compressed.is_odd,
+                ..compressed.clone()
+            };
+            let negated = flipped.to_pub_key().unwrap();
+            assert_ne!(negated, pk);
+            assert_eq!(negated.into_compressed(), flipped);
+        }
+    }
+
+    #[test]
+    fn compressed_pubkey_decompress_matches_reference() {
+        // Keys of the secrets 1 to 255, small enough to be valid scalars in either byte order
+        for secret in 1..=u8::MAX {
+            let kp = Keypair::from_hex(&format!("{:064x}", secret)).unwrap();
+            let compressed = kp.public.into_compressed();
+            for pk in [
+                compressed.clone(),
+                CompressedPubKey {
+                    is_odd: !compressed.is_odd,
+                    ..compressed.clone()
+                },
+            ] {
+                // The address round-trip used before direct decompression
+                let reference = PubKey::from_address(&pk.into_address()).unwrap();
+                assert_eq!(pk.to_pub_key(), Ok(reference));
+            }
+        }
+    }
+
+    #[test]
+    fn compressed_pubkey_decompress_rejects_invalid() {
+        for x in [Fp::zero(), Fp::from(2u64)] {
+            for is_odd in [false, true] {
+                let pk = CompressedPubKey { x, is_odd };
+                assert_eq!(pk.to_pub_key(), Err(InvalidPubKeyError));
+            }
+        }
+    }
+
+    #[test]
+    fn ord_pubkey_orders_consistently_with_eq() {
+        for a in ADDRESSES {
+            let a = OrdPubKey::from(CompressedPubKey::from_address(a).unwrap());
+            for b in ADDRESSES {
+                let b = OrdPubKey::from(CompressedPubKey::from_address(b).unwrap());
+                assert_eq!(a.cmp(&b) == Ordering::Equal, a == b);
+                assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+            }
+            // Same x with the other parity is a different key
+            let negated = OrdPubKey(CompressedPubKey {
+                is_odd: !a.0.is_odd,
+                ..a.0.clone()
+            });
+            assert_ne!(a.cmp(&negated), Ordering::Equal);
+        }
+    }
+
+    #[test]
+    fn compressed_pubkey_x_hex_roundtrip() -> Result<(), PubKeyHexError> {
+        let pk = CompressedPubKey::from_address(ADDRESSES[0]).unwrap();
+        assert_eq!(
+            pk.x_to_hex(),
+            "876fc2293ca688421553de93b14e8b25d08c7a9cb0d0ebc0ae19a78b3e1a4a1c"
+        );
+        for address in ADDRESSES {
+            let pk = CompressedPubKey::from_address(address).unwrap();
+            let recovered = CompressedPubKey::from_x_and_parity(&pk.x_to_hex(), pk.is_odd)?;
+            assert_eq!(recovered, pk);
+            assert_eq!(recovered.into_address(), address);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn compressed_pubkey_x_hex_rejects_out_of_range() {
+        // The modulus and all ones are not below the modulus
+        for x_hex in [
+            "01000000ed302d991bf94c09fc98462200000000000000000000000000000040",
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        ] {
+            assert_eq!(
+                CompressedPubKey::from_x_and_parity(x_hex, false),
+                Err(PubKeyHexError::NonCanonical)
+            );
+        }
+        // The modulus minus one is the largest canonical element
+        assert!(CompressedPubKey::from_x_and_parity(
+            "00000000ed302d991bf94c09fc98462200000000000000000000000000000040",
+            false
+        )
+        .is_ok());
+
+        assert_eq!(
+            CompressedPubKey::from_x_and_parity("00", true),
+            Err(PubKeyHexError::InvalidLength(1))
+        );
+        assert!(matches!(
+            CompressedPubKey::from_x_and_parity("zz", true),
+            Err(PubKeyHexError::InvalidHex(_))
+        ));
+    }
+}
@@ -0,0 +1,348 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers for working with Mina signatures
+//!
+//! The `Display` form of a [Signature] is the internal hex form used by the reference
+//! signer tests: the big-endian bytes of `rx` followed by the big-endian bytes of `s`,
+//! 128 hex characters in total. This is not the base58check form the node uses in json.
+//!
+//! The node's GraphQL also returns signatures as an object of two decimal strings,
+//! `{ "field": rx, "scalar": s }`, see [SignatureExt::to_json_object].
+//!
+//! The densest form, for storage, is [SignatureExt::to_bytes]: the 32 little-endian bytes
+//! of `rx` followed by those of `s`, the same encoding as in bin_prot.
+//!
+//! All forms only accept canonical encodings, with `rx` below the base field modulus and
+//! `s` below the scalar field modulus. Otherwise `s + q` would be a second valid encoding
+//! of the same signature, and commands could not be deduplicated by their signature.
+
+use crate::field_bytes::{fp_from_bytes_le, fp_to_bytes_le, FIELD_BYTES};
+use ark_ff::{BigInteger256, PrimeField};
+use mina_serialization_types::{json::SignatureJson, v1::SignatureV1};
+use num::{BigUint, Num};
+use proof_systems::mina_signer::Signature;
+use std::hash::{Hash, Hasher};
+use thiserror::Error;
+
+/// Error type for parsing a signature from its hex form
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum SignatureHexError {
+    /// The input is not valid hex
+    #[error("Invalid hex: {0}")]
+    InvalidHex(#[from] hex::FromHexError),
+    /// The input does not decode to exactly two field elements
+    #[error("Expected {} bytes but got {0}", 2 * FIELD_BYTES)]
+    InvalidLength(usize),
+    /// rx is not a canonical base field element
+    #[error("rx is not a valid base field element")]
+    InvalidRx,
+    /// s is not a canonical scalar field element
+    #[error("s is not a valid scalar field element")]
+    InvalidS,
+}
+
+/// Error type for parsing a signature from its json object form
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum SignatureObjectError {
+    /// The input is not an object with the named string member
+    #[error("Expected a string member {0}")]
+    MissingMember(&'static str),
+    /// The named member is not a decimal number
+    #[error("{0} is not a decimal number")]
+    InvalidDecimal(&'static str),
+    /// rx is not a canonical base field element
+    #[error("field is not a valid base field element")]
+    InvalidRx,
+    /// s is not a canonical scalar field element
+    #[error("scalar is not a valid scalar field element")]
+    InvalidS,
+}
+
+/// Error type for decoding a signature from its byte form
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureBytesError {
+    /// rx is not a canonical base field element
+    #[error("rx is not a valid base field element")]
+    InvalidRx,
+    /// s is not a canonical scalar field element
+    #[error("s is not a valid scalar field element")]
+    InvalidS,
+}
+
+const FIELD_MEMBER: &str = "field";
+const SCALAR_MEMBER: &str = "scalar";
+
+fn to_decimal<F: PrimeField<BigInt = BigInteger256>>(f: &F) -> String {
+    BigUint::from(f.into_repr()).to_str_radix(10)
+}
+
+fn from_decimal<F: PrimeField<BigInt = BigInteger256>>(
+    object: &serde_json::Value,
+    member: &'static str,
+) -> Result<Option<F>, SignatureObjectError> {
+    let decimal = object
+        .get(member)
+        .and_then(serde_json::Value::as_str)
+        .ok_or(SignatureObjectError::MissingMember(member))?;
+    let big = BigUint::from_str_radix(decimal, 10)
+        .map_err(|_| SignatureObjectError::InvalidDecimal(member))?;
+    // Values that do not fit in 256 bits are not canonical either
+    Ok(BigInteger256::try_from(big).ok().and_then(F::from_repr))
+}
+
+/// Extension methods for [Signature]
+pub trait SignatureExt: Sized {
+    /// Parse a signature from the hex form produced by its `Display` implementation
+    fn from_hex(hex: &str) -> Result<Self, SignatureHexError>;
+
+    /// Parse a signature from the base58check form the node uses in json
+    fn from_base58(s: &str) -> Result<Self, serde_json::Error>;
+
+    /// Encode as the base58check form the node uses in json
+    fn to_base58(&self) -> String;
+
+    /// Parse a signature from the `{ "field": rx, "scalar": s }` object form of the
+    /// node's GraphQL, with both members decimal strings
+    fn from_json_object(object: &serde_json::Value) -> Result<Self, SignatureObjectError>;
+
+    /// Encode as the `{ "field": rx, "scalar": s }` object form of the node's GraphQL
+    fn to_json_object(&self) -> serde_json::Value;
+
+    /// Decode a signature from the byte form produced by [SignatureExt::to_bytes]
+    fn from_bytes(bytes: &[u8; 64]) -> Result<Self, SignatureBytesError>;
+
+    /// Encode as the little-endian bytes of rx followed by those of s
+    fn to_bytes(&self) -> [u8; 64];
+
+    /// Feed the signature into a hasher, consistently with its `Eq`.
+    /// Lets types holding signatures implement [Hash] as the foreign signature type does not
+    fn hash_signature<H: Hasher>(&self, state: &mut H);
+}
+
+impl SignatureExt for Signature {
+    fn from_hex(hex: &str) -> Result<Self, SignatureHexError> {
+        let mut bytes = hex::decode(hex)?;
+        if bytes.len() != 2 * FIELD_BYTES {
+            return Err(SignatureHexError::InvalidLength(bytes.len()));
+        }
+        let (rx, s) = bytes.split_at_mut(FIELD_BYTES);
+        // Field elements are stored little-endian
+        rx.reverse();
+        s.reverse();
+        Ok(Signature::new(
+            fp_from_bytes_le(rx).ok_or(SignatureHexError::InvalidRx)?,
+            fp_from_bytes_le(s).ok_or(SignatureHexError::InvalidS)?,
+        ))
+    }
+
+    fn from_base58(s: &str) -> Result<Self, serde_json::Error> {
+        let json: SignatureJson = serde_json::from_value(serde_json::Value::String(s.into()))?;
+        Ok(SignatureV1::from(json).into())
+    }
+
+    fn to_base58(&self) -> String {
+        let json = serde_json::to_value(SignatureJson::from(SignatureV1::from(self)))
+            .expect("signatures always serialize to json");
+        json.as_str()
+            .expect("signatures serialize to a base58 string")
+            .into()
+    }
+
+    fn from_json_object(object: &serde_json::Value) -> Result<Self, SignatureObjectError> {
+        Ok(Signature::new(
+            from_decimal(object, FIELD_MEMBER)?.ok_or(SignatureObjectError::InvalidRx)?,
+            from_decimal(object, SCALAR_MEMBER)?.ok_or(SignatureObjectError::InvalidS)?,
+        ))
+    }
+
+    fn to_json_object(&self) -> serde_json::Value {
+        serde_json::json!({
+            FIELD_MEMBER: to_decimal(&self.rx),
+            SCALAR_MEMBER: to_decimal(&self.s),
+        })
+    }
+
+    fn from_bytes(bytes: &[u8; 64]) -> Result<Self, SignatureBytesError> {
+        let (rx, s) = bytes.split_at(FIELD_BYTES);
+        Ok(Signature::new(
+            fp_from_bytes_le(rx).ok_or(SignatureBytesError::InvalidRx)?,
+            fp_from_bytes_le(s).ok_or(SignatureBytesError::InvalidS)?,
+        ))
+    }
+
+    fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 2 * FIELD_BYTES];
+        let (rx, s) = bytes.split_at_mut(FIELD_BYTES);
+        rx.copy_from_slice(&fp_to_bytes_le(&self.rx));
+        s.copy_from_slice(&fp_to_bytes_le(&self.s));
+        bytes
+    }
+
+    fn hash_signature<H: Hasher>(&self, state: &mut H) {
+        self.rx.hash(state);
+        self.s.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proof_systems::mina_signer::{BaseField, ScalarField};
+
+    #[test]
+    fn signature_from_hex_rejects_malformed() {
+        assert!(matches!(
+            Signature::from_hex("zz"),
+            Err(SignatureHexError::InvalidHex(_))
+        ));
+        assert_eq!(
+            Signature::from_hex("00"),
+            Err(SignatureHexError::InvalidLength(1))
+        );
+        // Both halves set to all ones exceed their field moduli
+        assert_eq!(
+            Signature::from_hex(&format!("{}{}", "ff".repeat(32), "00".repeat(32))),
+            Err(SignatureHexError::InvalidRx)
+        );
+        assert_eq!(
+            Signature::from_hex(&format!("{}{}", "00".repeat(32), "ff".repeat(32))),
+            Err(SignatureHexError::InvalidS)
+        );
+    }
+
+    #[test]
+    fn signature_json_object_roundtrip() -> anyhow::Result<()> {
+        // Testnet signature of the test_1 payment vector
+        let signature = Signature::from_hex("11a36a8dfe5b857b95a2a7b7b17c62c3ea33411ae6f4eb3a907064aecae353c60794f1d0288322fe3f8bb69d6fabd4fd7c15f8d09f8783b2f087a80407e299af")?;
+        let object = signature.to_json_object();
+        assert_eq!(
+            object,
+            serde_json::json!({
+                "field": "7978049910726616927075298742385001574587620942310654323357397558995139646406",
+                "scalar": "3429352238474987065427486162608449491113877901219474382951875744532516739503",
+            })
+        );
+        assert_eq!(Signature::from_json_object(&object)?, signature);
+
+        assert_eq!(
+            Signature::from_json_object(&serde_json::json!({ "field": "1" })),
+            Err(SignatureObjectError::MissingMember("scalar"))
+        );
+        assert_eq!(
+            Signature::from_json_object(&serde_json::json!({ "field": "0x1", "scalar": "1" })),
+            Err(SignatureObjectError::InvalidDecimal("field"))
+        );
+        // 2^256 does not fit a field element
+        let too_big = BigUint::from(1u8) << 256;
+        assert_eq!(
+            Signature::from_json_object(&serde_json::json!({
+                "field": too_big.to_str_radix(10),
+                "scalar": "1",
+            })),
+            Err(SignatureObjectError::InvalidRx)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn signature_bytes_roundtrip() -> anyhow::Result<()> {
+        use ark_ff::{BigInteger, FpParameters};
+
+        // Testnet signature of the test_1 payment vector
+        let hex = "11a36a8dfe5b857b95a2a7b7b17c62c3ea33411ae6f4eb3a907064aecae353c60794f1d0288322fe3f8bb69d6fabd4fd7c15f8d09f8783b2f087a80407e299af";
+        let signature = Signature::from_hex(hex)?;
+        let bytes = signature.to_bytes();
+        assert_eq!(Signature::from_bytes(&bytes)?, signature);
+        // Each half is the big-endian hex half reversed
+        let mut be = hex::decode(hex)?;
+        be[..FIELD_BYTES].reverse();
+        be[FIELD_BYTES..].reverse();
+        assert_eq!(bytes.as_slice(), be.as_slice());
+
+        // The moduli themselves are the smallest non-canonical encodings
+        let mut rx_modulus = bytes;
+        rx_modulus[..FIELD_BYTES]
+            .copy_from_slice(&<BaseField as PrimeField>::Params::MODULUS.to_bytes_le());
+        assert_eq!(
+            Signature::from_bytes(&rx_modulus),
+            Err(SignatureBytesError::InvalidRx)
+        );
+        let mut s_modulus = bytes;
+        s_modulus[FIELD_BYTES..]
+            .copy_from_slice(&<ScalarField as PrimeField>::Params::MODULUS.to_bytes_le());
+        assert_eq!(
+            Signature::from_bytes(&s_modulus),
+            Err(SignatureBytesError::InvalidS)
+        );
+        assert_eq!(
+            Signature::from_bytes(&[0xff; 2 * FIELD_BYTES]),
+            Err(SignatureBytesError::InvalidRx)
+        );
+        Ok(())
+    }
+
+    fn be_hex(mut le_bytes: Vec<u8>) -> String {
+        le_bytes.reverse();
+        hex::encode(le_bytes)
+    }
+
+    #[test]
+    fn signature_rejects_non_canonical_s() -> anyhow::Result<()> {
+        use crate::user_commands::{
+            signed_command::builder::SignedTransferCommandBuilder, SignedCommand,
+        };
+        use ark_ff::{BigInteger, FpParameters, PrimeField};
+        use proof_systems::mina_signer::{Keypair, NetworkId};
+
+        let kp =
+            Keypair::from_hex("164244176fddb5d769b7de2027469d027ad428fadcc0c02396e6280142efb718")
+                .expect("failed to create keypair");
+        let payload = SignedTransferCommandBuilder::new(
+            kp.public.into_compressed(),
+            kp.public.into_compressed(),
+            1,
+            1,
+            0,
+        )
+        .build();
+        let signature = SignedCommand::from_payload(payload, kp, NetworkId::MAINNET).signature;
+        assert_eq!(Signature::from_hex(&signature.to_string())?, signature);
+        assert_eq!(Signature::from_base58(&signature.to_base58())?, signature);
+
+        let modulus = <ScalarField as PrimeField>::Params::MODULUS;
+        // s + q reduces to the same scalar, so it would verify if it were accepted
+        let mut malleated = signature.s.into_repr();
+        assert!(!malleated.add_nocarry(&modulus));
+        let mut minus_one = modulus;
+        assert!(!minus_one.sub_noborrow(&1u64.into()));
+
+        for (s, canonical) in [
+            (malleated.to_bytes_le(), false),
+            (modulus.to_bytes_le(), false),
+            (minus_one.to_bytes_le(), true),
+        ] {
+            let hex = format!(
+                "{}{}",
+                be_hex(fp_to_bytes_le(&signature.rx).to_vec()),
+                be_hex(s.clone())
+            );
+            let base58 = serde_json::to_value(SignatureJson(
+                (
+                    fp_to_bytes_le(&signature.rx),
+                    <[u8; 32]>::try_from(s).unwrap(),
+                )
+                    .into(),
+            ))?;
+            let base58 = base58.as_str().unwrap();
+            if canonical {
+                assert!(Signature::from_hex(&hex).is_ok());
+                assert!(Signature::from_base58(base58).is_ok());
+            } else {
+                assert_eq!(Signature::from_hex(&hex), Err(SignatureHexError::InvalidS));
+                assert!(Signature::from_base58(base58).is_err());
+            }
+        }
+        Ok(())
+    }
+}
@@ -7,15 +7,19 @@
 // TODO: Get clarification on all the fields of this type before documenting
 #![allow(missing_docs)]
 
-use crate::numbers::Amount;
+use crate::from_graphql_json::ToGraphQLJson;
+use crate::numbers::{AccountNonce, Amount};
+use crate::public_key::OrdPubKey;
 use crate::snark_work::TransactionSnarkWork;
 use crate::types::TokenId;
-use crate::user_commands::{SignedCommandPayload, UserCommand};
+use crate::user_commands::{SignedCommand, SignedCommandPayload, UserCommand, ValidationIssue};
 use crate::verifiable::Verifiable;
 use mina_serialization_types::{json::*, v1::*};
 use mina_serialization_types_macros::AutoFrom;
 use proof_systems::mina_signer::{CompressedPubKey, Signer};
 use smart_default::SmartDefault;
+use std::collections::BTreeMap;
+use thiserror::Error;
 use versioned::*;
 
 #[derive(Clone, Eq, PartialEq, Debug, Default, AutoFrom)]
@@ -27,9 +31,203 @@ pub struct StagedLedgerDiff {
 
 impl_from_with_proxy!(StagedLedgerDiff, StagedLedgerDiffV1, StagedLedgerDiffJson);
 
+/// How [StagedLedgerDiff::from_json_with_mode] handles user commands of kinds that are not
+/// modeled yet: snapp commands, and the token commands creating tokens, token accounts and
+/// minting tokens
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DecodeMode {
+    /// Fail on the first unsupported command, for consumers such as a syncing node that
+    /// would mis-account the block without it
+    Strict,
+    /// Drop unsupported commands and decode the others
+    #[default]
+    Lenient,
+}
+
+/// Error returned by [StagedLedgerDiff::from_json_with_mode]
+#[derive(Debug, Error)]
+pub enum DiffDecodeError {
+    /// A user command of this variant is not modeled, in [DecodeMode::Strict]
+    #[error("Unsupported user command {0}")]
+    UnsupportedCommand(String),
+    /// The diff json is invalid
+    #[error("Invalid staged ledger diff json: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// A user command dropped by [StagedLedgerDiff::from_json_with_skipped] as its variant is
+/// not modeled
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SkippedCommand {
+    /// Variant of the command, or of the body of a signed command, e.g. `Mint_tokens`
+    pub variant: String,
+    /// Index of the pre diff holding the command, 0 for the first one
+    pub pre_diff: usize,
+    /// Index of the command in the json commands of its pre diff
+    pub index: usize,
+    /// The command with its status, as the node emitted it
+    pub raw: serde_json::Value,
+}
+
+const SIGNED_COMMAND_VARIANT: &str = "Signed_command";
+const SUPPORTED_BODY_VARIANTS: [&str; 2] = ["Payment", "Stake_delegation"];
+
+/// Variant of the json user command if it is known but not modeled. Malformed commands
+/// are left for deserialization to report
+fn unsupported_variant(data: &serde_json::Value) -> Option<&str> {
+    let variant = data[0].as_str()?;
+    if variant != SIGNED_COMMAND_VARIANT {
+        return Some(variant);
+    }
+    data[1]["payload"]["body"][0]
+        .as_str()
+        .filter(|body| !SUPPORTED_BODY_VARIANTS.contains(body))
+}
+
+impl StagedLedgerDiff {
+    /// Iterates over all user commands in the diff, in the order they are applied
+    pub fn user_commands(&self) -> impl Iterator<Item = &UserCommand> {
+        std::iter::once(self.diff.diff_two())
+            .chain(self.diff.diff_one().iter())
+            .flat_map(|pre_diff| pre_diff.commands.iter().map(|cmd| &cmd.data))
+    }
+
+    /// Public keys of all signers and fee payers of the signed commands in the diff,
+    /// deduplicated and in order of first appearance
+    pub fn signers(&self) -> Vec<CompressedPubKey> {
+        let mut signers: Vec<CompressedPubKey> = Vec::new();
+        for cmd in self.user_commands() {
+            match cmd {
+                UserCommand::SignedCommand(sc) => {
+                    for pk in [&sc.signer, &sc.payload.common.fee_payer_pk] {
+                        if !signers.contains(pk) {
+                            signers.push(pk.clone());
+                        }
+                    }
+                }
+            }
+        }
+        signers
+    }
+
+    /// All user commands in the diff as a json array in the shape of the graphql API,
+    /// in the order they are applied
+    pub fn user_commands_graphql_json(&self) -> serde_json::Value {
+        serde_json::Value::Array(
+            self.user_commands()
+                .map(ToGraphQLJson::to_graphql_json)
+                .collect(),
+        )
+    }
+
+    /// Light validation of the signed commands of the diff against the fee payer nonces of
+    /// the ledger the diff applies to, keyed by fee payer. Returns the commands whose
+    /// signature does not verify with ctx or whose nonce is not the expected one, with
+    /// their issues, in the order they are applied.
+    ///
+    /// The expected nonce of a fee payer starts at its ledger nonce and increases by one
+    /// with each of its commands in the diff. Fee payers missing from nonces are reported
+    /// as [ValidationIssue::UnknownFeePayer]
+    pub fn invalid_commands<CTX>(
+        &self,
+        ctx: &mut CTX,
+        nonces: &BTreeMap<OrdPubKey, AccountNonce>,
+    ) -> Vec<(&SignedCommand, Vec<ValidationIssue>)>
+    where
+        CTX: Signer<SignedCommandPayload> + ?Sized,
+    {
+        let mut expected_nonces = nonces.clone();
+        let mut invalid = Vec::new();
+        for cmd in self.user_commands() {
+            let sc = match cmd {
+                UserCommand::SignedCommand(sc) => sc,
+            };
+            let mut issues = Vec::new();
+            if !sc.verify(ctx) {
+                issues.push(ValidationIssue::InvalidSignature);
+            }
+            let fee_payer = OrdPubKey(sc.payload.common.fee_payer_pk.clone());
+            match expected_nonces.get_mut(&fee_payer) {
+                Some(expected) => {
+                    if *expected != sc.nonce() {
+                        issues.push(ValidationIssue::NonceMismatch {
+                            expected: *expected,
+                            found: sc.nonce(),
+                        });
+                    }
+                    expected.0 = expected.0.saturating_add(1);
+                }
+                None => issues.push(ValidationIssue::UnknownFeePayer(fee_payer.0.into_address())),
+            }
+            if !issues.is_empty() {
+                invalid.push((sc, issues));
+            }
+        }
+        invalid
+    }
+
+    /// Decode a diff from the json the node emits, with unsupported user commands
+    /// handled according to mode
+    pub fn from_json_with_mode(
+        json: serde_json::Value,
+        mode: DecodeMode,
+    ) -> Result<Self, DiffDecodeError> {
+        Self::decode_json(json, mode).map(|(diff, _)| diff)
+    }
+
+    /// Decode a diff from the json the node emits in [DecodeMode::Lenient], also returning
+    /// the unsupported user commands that were dropped, in the order they appear in the
+    /// json, so that the gap in coverage can be reported
+    pub fn from_json_with_skipped(
+        json: serde_json::Value,
+    ) -> Result<(Self, Vec<SkippedCommand>), DiffDecodeError> {
+        Self::decode_json(json, DecodeMode::Lenient)
+    }
+
+    fn decode_json(
+        mut json: serde_json::Value,
+        mode: DecodeMode,
+    ) -> Result<(Self, Vec<SkippedCommand>), DiffDecodeError> {
+        let mut skipped = Vec::new();
+        let pre_diffs = json
+            .get_mut("diff")
+            .and_then(serde_json::Value::as_array_mut);
+        for (pre_diff_index, pre_diff) in pre_diffs.into_iter().flatten().enumerate() {
+            let commands = match pre_diff
+                .get_mut("commands")
+                .and_then(serde_json::Value::as_array_mut)
+            {
+                Some(commands) => commands,
+                None => continue,
+            };
+            let mut supported = Vec::with_capacity(commands.len());
+            for (index, cmd) in commands.drain(..).enumerate() {
+                match unsupported_variant(&cmd["data"]) {
+                    None => supported.push(cmd),
+                    Some(variant) if mode == DecodeMode::Strict => {
+                        return Err(DiffDecodeError::UnsupportedCommand(variant.into()))
+                    }
+                    Some(variant) => {
+                        let variant = variant.into();
+                        skipped.push(SkippedCommand {
+                            variant,
+                            pre_diff: pre_diff_index,
+                            index,
+                            raw: cmd,
+                        })
+                    }
+                }
+            }
+            *commands = supported;
+        }
+        let json: StagedLedgerDiffJson = serde_json::from_value(json)?;
+        Ok((json.into(), skipped))
+    }
+}
+
 impl<CTX> Verifiable<CTX> for StagedLedgerDiff
 where
-    CTX: Signer<SignedCommandPayload>,
+    CTX: Signer<SignedCommandPayload> + ?Sized,
 {
     // StagedLedgerDiff is considered valid if:
     // - All PreDiffs are valid
@@ -70,7 +268,7 @@ pub struct StagedLedgerPreDiff {
 
 impl<CTX> Verifiable<CTX> for StagedLedgerPreDiff
 where
-    CTX: Signer<SignedCommandPayload>,
+    CTX: Signer<SignedCommandPayload> + ?Sized,
 {
     // PreDiff is considered valid if:
     // - all commands are valid
@@ -202,3 +400,236 @@ pub struct FeeTransferBalanceData {
     // FIXME: No test coverage yet
     pub receiver2_balance: Option<Amount>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers::{AccountNonce, GlobalSlotNumber};
+    use crate::user_commands::signed_command::{test_util::payment_test_1, StakeDelegation};
+    use crate::user_commands::{
+        SignedCommand, SignedCommandMemo, SignedCommandPayloadBody, SignedCommandPayloadCommon,
+    };
+    use proof_systems::mina_signer::{Keypair, NetworkId};
+
+    fn applied(data: UserCommand) -> UserCommandWithStatus {
+        UserCommandWithStatus {
+            data,
+            status: TransactionStatus::Applied(
+                TransactionStatusAuxiliaryData {
+                    fee_payer_account_creation_fee_paid: None,
+                    receiver_account_creation_fee_paid: None,
+                    created_token: None,
+                },
+                TransactionStatusBalanceData {
+                    fee_payer_balance: None,
+                    source_balance: None,
+                    receiver_balance: None,
+                },
+            ),
+        }
+    }
+
+    fn pk(address: &str) -> CompressedPubKey {
+        CompressedPubKey::from_address(address).expect("invalid address")
+    }
+
+    /// A diff with a payment whose fee is paid by a third account followed by a delegation
+    fn two_command_diff() -> StagedLedgerDiff {
+        let delegator =
+            Keypair::from_hex("3414fc16e86e6ac272fda03cf8dcb4d7d47af91b4b726494dab43bf773ce1779")
+                .unwrap();
+
+        let (mut payment, payer) = payment_test_1();
+        payment.common.fee_payer_pk = pk("B62qoqiAgERjCjXhofXiD7cMLJSKD8hE8ZtMh4jX5MPNgKB4CFxxm1N");
+        let delegation = SignedCommandPayload {
+            common: SignedCommandPayloadCommon {
+                fee: Amount(10100000),
+                fee_token: TokenId(1),
+                fee_payer_pk: delegator.public.into_compressed(),
+                nonce: AccountNonce(4),
+                valid_until: GlobalSlotNumber::MAX,
+                memo: SignedCommandMemo::default(),
+            },
+            body: SignedCommandPayloadBody::StakeDelegation(StakeDelegation::SetDelegate {
+                delegator: delegator.public.into_compressed(),
+                new_delegate: pk("B62qrKG4Z8hnzZqp1AL8WsQhQYah3quN1qUj3SyfJA8Lw135qWWg1mi"),
+            }),
+        };
+
+        StagedLedgerDiff {
+            diff: StagedLedgerDiffTuple(
+                StagedLedgerPreDiff {
+                    commands: vec![
+                        applied(UserCommand::SignedCommand(SignedCommand::from_payload(
+                            payment,
+                            payer,
+                            NetworkId::MAINNET,
+                        ))),
+                        applied(UserCommand::SignedCommand(SignedCommand::from_payload(
+                            delegation,
+                            delegator,
+                            NetworkId::MAINNET,
+                        ))),
+                    ],
+                    ..Default::default()
+                },
+                None,
+            ),
+        }
+    }
+
+    #[test]
+    fn staged_ledger_diff_signers() {
+        let diff = two_command_diff();
+        assert_eq!(diff.user_commands().count(), 2);
+        assert_eq!(
+            diff.signers(),
+            vec![
+                pk("B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV"),
+                pk("B62qoqiAgERjCjXhofXiD7cMLJSKD8hE8ZtMh4jX5MPNgKB4CFxxm1N"),
+                pk("B62qoG5Yk4iVxpyczUrBNpwtx2xunhL48dydN53A2VjoRwF8NUTbVr4"),
+            ]
+        );
+    }
+
+    #[test]
+    fn staged_ledger_diff_invalid_commands() {
+        use crate::user_commands::SignatureScheme;
+        use proof_systems::mina_signer;
+
+        let diff = two_command_diff();
+        let mut ctx: Box<dyn Signer<SignedCommandPayload>> = match SignatureScheme::DEFAULT {
+            SignatureScheme::Legacy => Box::new(mina_signer::create_legacy(NetworkId::MAINNET)),
+            SignatureScheme::Kimchi => Box::new(mina_signer::create_kimchi(NetworkId::MAINNET)),
+        };
+        let payment_fee_payer = "B62qoqiAgERjCjXhofXiD7cMLJSKD8hE8ZtMh4jX5MPNgKB4CFxxm1N";
+        let delegator = "B62qoG5Yk4iVxpyczUrBNpwtx2xunhL48dydN53A2VjoRwF8NUTbVr4";
+
+        let nonces = BTreeMap::from([
+            (OrdPubKey(pk(payment_fee_payer)), AccountNonce(16)),
+            (OrdPubKey(pk(delegator)), AccountNonce(4)),
+        ]);
+        assert!(diff.invalid_commands(&mut *ctx, &nonces).is_empty());
+
+        // The delegation nonce is one ahead of the ledger
+        let nonces = BTreeMap::from([
+            (OrdPubKey(pk(payment_fee_payer)), AccountNonce(16)),
+            (OrdPubKey(pk(delegator)), AccountNonce(3)),
+        ]);
+        let invalid = diff.invalid_commands(&mut *ctx, &nonces);
+        assert_eq!(invalid.len(), 1);
+        let (cmd, issues) = &invalid[0];
+        assert_eq!(cmd.signer_address(), delegator);
+        assert_eq!(
+            issues,
+            &vec![ValidationIssue::NonceMismatch {
+                expected: AccountNonce(3),
+                found: AccountNonce(4),
+            }]
+        );
+
+        let nonces = BTreeMap::from([(OrdPubKey(pk(delegator)), AccountNonce(4))]);
+        let invalid = diff.invalid_commands(&mut *ctx, &nonces);
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(
+            invalid[0].1,
+            vec![ValidationIssue::UnknownFeePayer(payment_fee_payer.into())]
+        );
+    }
+
+    #[test]
+    fn staged_ledger_diff_decode_modes() -> anyhow::Result<()> {
+        let diff = two_command_diff();
+        let mut json = serde_json::to_value(StagedLedgerDiffJson::from(diff.clone()))?;
+        let mut mint = json["diff"][0]["commands"][0].clone();
+        mint["data"][1]["payload"]["body"] = serde_json::json!([
+            "Mint_tokens",
+            {
+                "token_id": "2",
+                "token_owner_pk": "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+                "receiver_pk": "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+                "amount": "1000"
+            }
+        ]);
+        json["diff"][0]["commands"]
+            .as_array_mut()
+            .expect("Expected a json array")
+            .insert(1, mint);
+
+        // Plain deserialization fails on the unmodeled variant
+        assert!(serde_json::from_value::<StagedLedgerDiffJson>(json.clone()).is_err());
+        assert_eq!(DecodeMode::default(), DecodeMode::Lenient);
+        assert_eq!(
+            StagedLedgerDiff::from_json_with_mode(json.clone(), DecodeMode::Lenient)?,
+            diff
+        );
+        assert!(matches!(
+            StagedLedgerDiff::from_json_with_mode(json, DecodeMode::Strict),
+            Err(DiffDecodeError::UnsupportedCommand(variant)) if variant == "Mint_tokens"
+        ));
+
+        let (decoded, skipped) = StagedLedgerDiff::from_json_with_skipped(json.clone())?;
+        assert_eq!(decoded, diff);
+        assert_eq!(
+            skipped,
+            vec![SkippedCommand {
+                variant: "Mint_tokens".into(),
+                pre_diff: 0,
+                index: 1,
+                raw: json["diff"][0]["commands"][1].clone(),
+            }]
+        );
+
+        // Both modes decode diffs with supported commands only
+        let json = serde_json::to_value(StagedLedgerDiffJson::from(diff.clone()))?;
+        assert_eq!(
+            StagedLedgerDiff::from_json_with_mode(json, DecodeMode::Strict)?,
+            diff
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn staged_ledger_diff_user_commands_graphql_json() {
+        let diff = two_command_diff();
+        let json = diff.user_commands_graphql_json();
+        let commands = json.as_array().expect("Expected a json array");
+        assert_eq!(commands.len(), 2);
+
+        assert_eq!(commands[0]["kind"], "PAYMENT");
+        assert_eq!(commands[0]["isDelegation"], false);
+        assert_eq!(commands[0]["nonce"], 16);
+        assert_eq!(
+            commands[0]["feePayer"]["publicKey"],
+            "B62qoqiAgERjCjXhofXiD7cMLJSKD8hE8ZtMh4jX5MPNgKB4CFxxm1N"
+        );
+        assert_eq!(
+            commands[0]["source"]["publicKey"],
+            "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV"
+        );
+        assert_eq!(
+            commands[0]["receiver"]["publicKey"],
+            "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt"
+        );
+        assert_eq!(commands[0]["amount"], "1729000000000");
+        assert_eq!(commands[0]["fee"], "2000000000");
+
+        assert_eq!(commands[1]["kind"], "STAKE_DELEGATION");
+        assert_eq!(commands[1]["isDelegation"], true);
+        assert_eq!(commands[1]["nonce"], 4);
+        assert_eq!(
+            commands[1]["source"]["publicKey"],
+            "B62qoG5Yk4iVxpyczUrBNpwtx2xunhL48dydN53A2VjoRwF8NUTbVr4"
+        );
+        assert_eq!(
+            commands[1]["receiver"]["publicKey"],
+            "B62qrKG4Z8hnzZqp1AL8WsQhQYah3quN1qUj3SyfJA8Lw135qWWg1mi"
+        );
+        assert_eq!(commands[1]["amount"], "0");
+        assert_eq!(commands[1]["fee"], "10100000");
+
+        for (json, cmd) in commands.iter().zip(diff.user_commands()) {
+            assert_eq!(json, &cmd.to_graphql_json());
+        }
+    }
+}
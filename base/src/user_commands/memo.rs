@@ -8,26 +8,53 @@ use mina_serialization_types::{json::*, *};
 use mina_serialization_types_macros::AutoFrom;
 use thiserror::Error;
 
-/// A memo byte strong for a signed command
-#[derive(Clone, Default, Eq, PartialEq, Debug, derive_more::From, derive_more::Into, AutoFrom)]
+/// A memo byte strong for a signed command.
+/// Every way of building a memo gives the same canonical bytes for the same content, so
+/// equal memos compare equal however they were built
+#[derive(Clone, Eq, PartialEq, Hash, Debug, derive_more::From, derive_more::Into, AutoFrom)]
 #[auto_from(mina_serialization_types::staged_ledger_diff::SignedCommandMemo)]
 #[auto_from(mina_serialization_types::staged_ledger_diff::SignedCommandMemoJson)]
 pub struct SignedCommandMemo(pub Vec<u8>);
 
 impl_strconv_via_json!(SignedCommandMemo, SignedCommandMemoJson);
 
+const DIGEST_LEN: usize = 32;
+const MAX_INPUT_STRING_LENGTH: usize = DIGEST_LEN;
+const MEMO_LEN: usize = DIGEST_LEN + 2;
+const TAG_INDEX: usize = 0;
+const LEN_INDEX: usize = 1;
+const BYTES_TAG: u8 = 1;
+
 impl SignedCommandMemo {
-    /// Try build from text
-    pub fn try_from_text(s: impl AsRef<[u8]>) -> Result<Self, SignedCommandMemoError> {
-        const DIGEST_LEN: usize = 32;
-        const MAX_INPUT_STRING_LENGTH: usize = DIGEST_LEN;
-        const MEMO_LEN: usize = DIGEST_LEN + 2;
-        const TAG_INDEX: usize = 0;
-        const LEN_INDEX: usize = 1;
-        const BYTES_TAG: u8 = 1;
+    /// The memo with no content, the same bytes as `try_from_text("")` and as the empty
+    /// memo of the daemon
+    pub fn empty() -> Self {
+        let mut v = vec![0; MEMO_LEN];
+        v[TAG_INDEX] = BYTES_TAG;
+        Self(v)
+    }
+
+    /// Try build from text of at most 32 bytes of valid utf8.
+    /// The memo is padded the way the daemon does: byte 0 is the bytes tag `0x01`, byte 1
+    /// the length of the text, followed by the text and zeros up to 34 bytes. Signatures
+    /// commit to all 34 bytes, so any other padding gives signatures the daemon rejects
+    pub fn try_from_text(s: impl AsRef<[u8]>) -> Result<Self, MemoTextError> {
         let s = s.as_ref();
         if s.len() > MAX_INPUT_STRING_LENGTH {
-            return Err(SignedCommandMemoError::StringTooLong);
+            return Err(MemoTextError::TooLong(s.len()));
+        }
+        if std::str::from_utf8(s).is_err() {
+            return Err(MemoTextError::NonUtf8);
+        }
+        Self::try_from_bytes(s)
+    }
+
+    /// Try build from at most 32 bytes of arbitrary data, which need not be utf8.
+    /// The input is the memo content only: the tag, length and zero padding are added the
+    /// same way as by [SignedCommandMemo::try_from_text]
+    pub fn try_from_bytes(s: &[u8]) -> Result<Self, MemoTextError> {
+        if s.len() > MAX_INPUT_STRING_LENGTH {
+            return Err(MemoTextError::TooLong(s.len()));
         }
         let mut v = vec![0; MEMO_LEN];
         v[TAG_INDEX] = BYTES_TAG;
@@ -43,14 +70,52 @@ impl SignedCommandMemo {
         let len = self.0[1] as usize;
         String::from_utf8_lossy(&self.0[2..(2 + len)]).into()
     }
+
+    /// The text content, or None if the memo does not hold utf8 text
+    pub fn as_text(&self) -> Option<String> {
+        std::str::from_utf8(self.as_bytes()?).ok().map(Into::into)
+    }
+
+    /// The content bytes, the inverse of [SignedCommandMemo::try_from_bytes].
+    /// None if the memo is not tagged as holding bytes or its length overruns it
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self.0.as_slice() {
+            [BYTES_TAG, len, rest @ ..] => rest.get(..*len as usize),
+            _ => None,
+        }
+    }
+
+    /// Same as `==` but looks at every byte instead of stopping at the first difference,
+    /// e.g. to match the memo of an incoming payment to an expected invoice memo without
+    /// leaking through timing how much of it an attacker guessed right.
+    /// Memos are all the same length, only a malformed one returns early.
+    /// This is best effort, the compiler is not prevented from optimizing the loop
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(&other.0)
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+impl Default for SignedCommandMemo {
+    fn default() -> Self {
+        Self::empty()
+    }
 }
 
-/// Error type for converting memo types
-#[derive(Debug, Error)]
-pub enum SignedCommandMemoError {
-    /// Tried to build a memo from a string that is too long to fit
-    #[error("Input string is too long")]
-    StringTooLong,
+/// Error type for building a memo from text
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum MemoTextError {
+    /// Tried to build a memo from a string of this many bytes, which is too long to fit
+    #[error("Input string is {0} bytes long, at most 32 fit in a memo")]
+    TooLong(usize),
+    /// Tried to build a memo from bytes that are not valid utf8
+    #[error("Input string is not valid utf8")]
+    NonUtf8,
 }
 
 #[cfg(test)]
@@ -58,6 +123,52 @@ mod tests {
     use super::*;
     use crate::*;
 
+    #[test]
+    fn memo_ct_eq_matches_eq() -> anyhow::Result<()> {
+        let memos = [
+            SignedCommandMemo::default(),
+            SignedCommandMemo::try_from_text("")?,
+            SignedCommandMemo::try_from_text("invoice 1729")?,
+            SignedCommandMemo::try_from_text("invoice 1728")?,
+            SignedCommandMemo::try_from_bytes(b"invoice 1729")?,
+            SignedCommandMemo::try_from_bytes(&[0xff; 32])?,
+            SignedCommandMemo(vec![BYTES_TAG, 0]),
+        ];
+        for a in &memos {
+            for b in &memos {
+                assert_eq!(a.ct_eq(b), a == b, "{a:?} {b:?}");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn memo_construction_paths_agree() -> anyhow::Result<()> {
+        // Empty memo of a mainnet stake delegation
+        let empty = [
+            SignedCommandMemo::try_from_text("")?,
+            SignedCommandMemo::try_from_bytes(&[])?,
+            SignedCommandMemo::from_str("E4YM2vTHhWEg66xpj52JErHUBU4pZ1yageL4TVDDpTTSsv8mK6YaH")?,
+            SignedCommandMemo::default(),
+            SignedCommandMemo::empty(),
+        ];
+        for memo in &empty {
+            assert_eq!(memo, &empty[0]);
+            assert_eq!(memo.0.len(), MEMO_LEN);
+            assert_eq!(memo.as_text(), Some("".into()));
+        }
+
+        let text = [
+            SignedCommandMemo::try_from_text("FPayment")?,
+            SignedCommandMemo::try_from_bytes(b"FPayment")?,
+            SignedCommandMemo::from_str("E4Yd7qwaRCHR6t7i6ToM98eSUy5eKKadQUPZX7Vpw4CWBvWyd8fzK")?,
+        ];
+        for memo in &text {
+            assert_eq!(memo, &text[0]);
+        }
+        Ok(())
+    }
+
     #[test]
     fn memo_roundtrip() -> anyhow::Result<()> {
         let bs58_encoded = "E4Yd7qwaRCHR6t7i6ToM98eSUy5eKKadQUPZX7Vpw4CWBvWyd8fzK";
@@ -70,4 +181,114 @@ mod tests {
         assert_eq!(&memo.to_text(), text_expected);
         Ok(())
     }
+
+    #[test]
+    fn memo_from_text_pads_like_daemon() -> anyhow::Result<()> {
+        use crate::user_commands::signed_command::{
+            test_util::payment_test_1, NetworkSignedCommand, SignatureScheme,
+        };
+        use proof_systems::mina_signer::NetworkId;
+
+        let mut expected = vec![0x01, 0x0b];
+        expected.extend_from_slice(b"Hello Mina!");
+        expected.resize(MEMO_LEN, 0);
+        let memo = SignedCommandMemo::try_from_text("Hello Mina!")?;
+        assert_eq!(memo.0, expected);
+
+        // The mainnet signature of sign_payment_test_1 only verifies for these exact bytes
+        let (mut payload, keypair) = payment_test_1();
+        payload.common.memo = SignedCommandMemo(expected);
+        let signed = NetworkSignedCommand::from_payload(
+            payload,
+            keypair,
+            NetworkId::MAINNET,
+            SignatureScheme::Legacy,
+        );
+        assert_eq!(
+            signed.command.signature.to_string(),
+            "124c592178ed380cdffb11a9f8e1521bf940e39c13f37ba4c55bb4454ea69fba3c3595a55b06dac86261bb8ab97126bf3f7fff70270300cb97ff41401a5ef789"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn memo_from_text_max_length() -> anyhow::Result<()> {
+        let text = "01234567890123456789012345678901";
+        assert_eq!(SignedCommandMemo::try_from_text(text)?.to_text(), text);
+        Ok(())
+    }
+
+    #[test]
+    fn memo_from_bytes() -> anyhow::Result<()> {
+        // Same layout as text memos, without requiring utf8
+        let memo = SignedCommandMemo::try_from_bytes(&[0xff, 0xfe])?;
+        assert_eq!(&memo.0[..4], &[1, 2, 0xff, 0xfe]);
+        assert_eq!(memo.0.len(), 34);
+        assert_eq!(memo.as_text(), None);
+        assert_eq!(
+            SignedCommandMemo::try_from_bytes(b"FPayment")?,
+            SignedCommandMemo::try_from_text("FPayment")?
+        );
+        assert_eq!(
+            SignedCommandMemo::try_from_bytes(&[0; 33]),
+            Err(MemoTextError::TooLong(33))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn memo_binary_content_roundtrip() -> anyhow::Result<()> {
+        let contents: [&[u8]; 4] = [&[0xff, 0xfe], &[0x80; 32], &[b'a', 0xc3], &[0, 0xff, 0]];
+        for content in contents {
+            let memo = SignedCommandMemo::try_from_bytes(content)?;
+            assert_eq!(memo.as_bytes(), Some(content));
+            assert_eq!(memo.as_text(), None);
+            // Through the base58check encoding used in json
+            let recovered = SignedCommandMemo::from_str(&memo.to_string())?;
+            assert_eq!(recovered.as_bytes(), Some(content));
+            assert_eq!(recovered, memo);
+        }
+        assert_eq!(SignedCommandMemo(vec![]).as_bytes(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn memo_from_text_too_long() {
+        assert_eq!(
+            SignedCommandMemo::try_from_text("012345678901234567890123456789012"),
+            Err(MemoTextError::TooLong(33))
+        );
+        // Length is counted in bytes, not characters
+        assert_eq!(
+            SignedCommandMemo::try_from_text("é".repeat(17)),
+            Err(MemoTextError::TooLong(34))
+        );
+    }
+
+    #[test]
+    fn memo_as_text() {
+        assert_eq!(
+            SignedCommandMemo::try_from_text("FPayment")
+                .unwrap()
+                .as_text(),
+            Some("FPayment".into())
+        );
+        assert_eq!(
+            SignedCommandMemo::try_from_text("").unwrap().as_text(),
+            Some("".into())
+        );
+        assert_eq!(SignedCommandMemo(vec![]).as_text(), None);
+        // Length past the end of the memo
+        assert_eq!(SignedCommandMemo(vec![1, 3, b'a']).as_text(), None);
+        // Not utf8
+        assert_eq!(SignedCommandMemo(vec![1, 2, 0xff, 0xfe]).as_text(), None);
+    }
+
+    #[test]
+    fn memo_from_text_non_utf8() {
+        assert_eq!(
+            SignedCommandMemo::try_from_text([0x46, 0xff, 0xfe]),
+            Err(MemoTextError::NonUtf8)
+        );
+    }
 }
@@ -13,14 +13,27 @@ use crate::verifiable::Verifiable;
 pub use memo::SignedCommandMemo;
 pub use payment::PaymentPayload;
 pub use signed_command::{
+    detect_nonce_gaps, group_by_fee_payer, verify_signature, CommandKindError, DelegationCommand,
+    FrameError, NetworkCheckError, NetworkSignedCommand, PaymentCommand, SignatureScheme,
     SignedCommand, SignedCommandPayload, SignedCommandPayloadBody, SignedCommandPayloadCommon,
+    SigningContext, TokenError, TransactionTag, ValidationIssue,
 };
 
+use crate::from_graphql_json::ToGraphQLJson;
 use mina_serialization_types::json::UserCommandJson;
 use mina_serialization_types_macros::AutoFrom;
-use proof_systems::mina_signer::Signer;
+use proof_systems::mina_hasher::{self, Fp, Hashable, Hasher};
+use proof_systems::mina_signer::{NetworkId, Signer};
 use versioned::*;
 
+/// Hash a signable structure with the legacy Poseidon parameters, initializing the hasher
+/// with the domain string of the network. Signable types only need to implement [Hashable]
+/// with the network as the domain parameter to be hashed the same way as commands
+pub fn hash_with_domain<H: Hashable<D = NetworkId>>(h: &H, network: NetworkId) -> Fp {
+    let mut hasher = mina_hasher::create_legacy::<H>(network);
+    hasher.hash(h)
+}
+
 /// The top level user command type
 /// This is the output of the command builders
 #[derive(Clone, Eq, PartialEq, Debug, AutoFrom)]
@@ -37,9 +50,17 @@ impl_from_with_proxy!(
     UserCommandJson
 );
 
+impl ToGraphQLJson for UserCommand {
+    fn to_graphql_json(&self) -> serde_json::Value {
+        match self {
+            UserCommand::SignedCommand(sc) => sc.to_graphql_json(),
+        }
+    }
+}
+
 impl<CTX> Verifiable<CTX> for UserCommand
 where
-    CTX: Signer<SignedCommandPayload>,
+    CTX: Signer<SignedCommandPayload> + ?Sized,
 {
     fn verify(&self, ctx: &mut CTX) -> bool {
         match self {
@@ -47,3 +68,27 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_commands::signed_command::test_util::payment_test_1;
+
+    #[test]
+    fn hash_with_domain_payload() {
+        let (payload, _) = payment_test_1();
+
+        for network in [NetworkId::TESTNET, NetworkId::MAINNET] {
+            let mut hasher = mina_hasher::create_legacy::<SignedCommandPayload>(network.clone());
+            assert_eq!(
+                hash_with_domain(&payload, network.clone()),
+                hasher.hash(&payload)
+            );
+        }
+        // The domain string separates the networks
+        assert_ne!(
+            hash_with_domain(&payload, NetworkId::TESTNET),
+            hash_with_domain(&payload, NetworkId::MAINNET)
+        );
+    }
+}
@@ -4,9 +4,11 @@
 //! Payment commands are for transfering some token amounts between accounts
 
 use crate::numbers::{Amount, TokenId};
+use crate::public_key::CompressedPubKeyExt;
 
 use mina_serialization_types_macros::AutoFrom;
 use proof_systems::mina_signer::CompressedPubKey;
+use std::hash::{Hash, Hasher};
 
 /// The data specific to payload commands
 #[derive(Clone, Eq, PartialEq, Debug, AutoFrom)]
@@ -21,3 +23,98 @@ pub struct PaymentPayload {
     /// The ammount of that token to transfer
     pub amount: Amount,
 }
+
+impl Hash for PaymentPayload {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.source_pk.hash_key(state);
+        self.receiver_pk.hash_key(state);
+        self.token_id.hash(state);
+        self.amount.hash(state);
+    }
+}
+
+impl PaymentPayload {
+    /// Payment of amount of the given token from source to receiver
+    pub fn new(
+        source_pk: CompressedPubKey,
+        receiver_pk: CompressedPubKey,
+        amount: impl Into<Amount>,
+        token_id: impl Into<TokenId>,
+    ) -> Self {
+        Self {
+            source_pk,
+            receiver_pk,
+            token_id: token_id.into(),
+            amount: amount.into(),
+        }
+    }
+
+    /// Payment of amount of the default token from source to receiver
+    pub fn with_default_token(
+        source_pk: CompressedPubKey,
+        receiver_pk: CompressedPubKey,
+        amount: impl Into<Amount>,
+    ) -> Self {
+        Self::new(source_pk, receiver_pk, amount, TokenId::default_token())
+    }
+
+    /// Account to transfer from
+    pub fn source_pk(&self) -> &CompressedPubKey {
+        &self.source_pk
+    }
+
+    /// Account to transfer to
+    pub fn receiver_pk(&self) -> &CompressedPubKey {
+        &self.receiver_pk
+    }
+
+    /// The token to transfer
+    pub fn token_id(&self) -> &TokenId {
+        &self.token_id
+    }
+
+    /// The amount of that token to transfer
+    pub fn amount(&self) -> Amount {
+        self.amount
+    }
+
+    /// If the source and receiver are the same account
+    pub fn is_self_payment(&self) -> bool {
+        self.source_pk == self.receiver_pk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_commands::SignedCommandPayloadBody;
+
+    #[test]
+    fn payment_payload_new() {
+        let source = CompressedPubKey::from_address(
+            "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+        )
+        .unwrap();
+        let receiver = CompressedPubKey::from_address(
+            "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+        )
+        .unwrap();
+
+        let payload =
+            PaymentPayload::with_default_token(source.clone(), receiver.clone(), 1729000000000);
+        assert_eq!(payload.source_pk(), &source);
+        assert_eq!(payload.receiver_pk(), &receiver);
+        assert_eq!(payload.amount(), Amount(1729000000000));
+        assert_eq!(payload.token_id(), &TokenId::default_token());
+        assert_eq!(
+            payload,
+            PaymentPayload::new(source, receiver, 1729000000000, 1)
+        );
+
+        let body = SignedCommandPayloadBody::PaymentPayload(payload.clone());
+        match body {
+            SignedCommandPayloadBody::PaymentPayload(p) => assert_eq!(p, payload),
+            _ => panic!("Expected a payment body"),
+        }
+    }
+}
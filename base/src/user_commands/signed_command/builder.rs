@@ -3,14 +3,17 @@
 
 //! Helpers for building a user command
 
-use proof_systems::mina_signer::CompressedPubKey;
+use proof_systems::mina_signer::{CompressedPubKey, Keypair, NetworkId};
+use thiserror::Error;
 
 use crate::numbers::{AccountNonce, Amount, GlobalSlotNumber, TokenId};
+use crate::public_key::CompressedPubKeyExt;
 use crate::user_commands::{
     PaymentPayload, SignedCommandPayload, SignedCommandPayloadBody, SignedCommandPayloadCommon,
 };
 
-use super::SignedCommandMemo;
+use super::{SignedCommand, SignedCommandMemo, StakeDelegation, TokenError};
+use crate::user_commands::memo::MemoTextError;
 
 /// A builder for UserCommands to transfer funds
 pub struct SignedTransferCommandBuilder {
@@ -24,6 +27,10 @@ pub struct SignedTransferCommandBuilder {
     memo: SignedCommandMemo,
     fee_payer_pk: CompressedPubKey,
     valid_until: GlobalSlotNumber,
+    disallow_self_payment: bool,
+    require_nonzero_amount: bool,
+    require_within_supply: bool,
+    reject_expired_at: Option<GlobalSlotNumber>,
 }
 
 impl SignedTransferCommandBuilder {
@@ -39,13 +46,68 @@ impl SignedTransferCommandBuilder {
             to,
             from: from.clone(),
             amount: amount.into(),
-            transfer_token: TokenId(1),
-            fee_token: TokenId(1),
+            transfer_token: TokenId::default_token(),
+            fee_token: TokenId::default_token(),
             fee: fee.into(),
             nonce: nonce.into(),
             fee_payer_pk: from,
             memo: SignedCommandMemo::default(),
             valid_until: GlobalSlotNumber::MAX,
+            disallow_self_payment: false,
+            require_nonzero_amount: false,
+            require_within_supply: false,
+            reject_expired_at: None,
+        }
+    }
+
+    /// Same as [SignedTransferCommandBuilder::new] but parses the source and receiver
+    /// from their base58 addresses, rejecting the identity and points off the curve as
+    /// [CompressedPubKeyExt::from_address_checked] does
+    pub fn from_addresses<T: Into<Amount>, TT: Into<Amount>, TTT: Into<AccountNonce>>(
+        from: &str,
+        to: &str,
+        amount: T,
+        fee: TT,
+        nonce: TTT,
+    ) -> Result<Self, AddressError> {
+        let from = CompressedPubKey::from_address_checked(from)
+            .map_err(|e| AddressError::InvalidSource(from.into(), e.to_string()))?;
+        let to = CompressedPubKey::from_address_checked(to)
+            .map_err(|e| AddressError::InvalidReceiver(to.into(), e.to_string()))?;
+        Ok(Self::new(from, to, amount, fee, nonce))
+    }
+
+    /// Repopulate a builder from the payload of an existing payment, ignoring its signature.
+    /// This allows replacing a pending command, e.g. with a higher fee.
+    /// Returns None if the command is not a payment
+    pub fn from_command(command: &SignedCommand) -> Option<Self> {
+        let common = &command.payload.common;
+        match &command.payload.body {
+            SignedCommandPayloadBody::PaymentPayload(payment) => Some(Self {
+                to: payment.receiver_pk().clone(),
+                from: payment.source_pk().clone(),
+                amount: payment.amount(),
+                transfer_token: payment.token_id().clone(),
+                fee_token: common.fee_token.clone(),
+                fee: common.fee,
+                nonce: common.nonce,
+                memo: common.memo.clone(),
+                fee_payer_pk: common.fee_payer_pk.clone(),
+                valid_until: common.valid_until,
+                disallow_self_payment: false,
+                require_nonzero_amount: false,
+                require_within_supply: false,
+                reject_expired_at: None,
+            }),
+            SignedCommandPayloadBody::StakeDelegation(_) => None,
+        }
+    }
+
+    /// Set the fee paid to the block producer
+    pub fn fee<T: Into<Amount>>(self, fee: T) -> Self {
+        Self {
+            fee: fee.into(),
+            ..self
         }
     }
 
@@ -78,6 +140,261 @@ impl SignedTransferCommandBuilder {
         Self { memo, ..self }
     }
 
+    /// Set a memo holding the given bytes, failing if they do not fit
+    pub fn memo_bytes(self, bytes: &[u8]) -> Result<Self, MemoTextError> {
+        Ok(self.memo(SignedCommandMemo::try_from_bytes(bytes)?))
+    }
+
+    /// Set a memo holding the given text, failing if it does not fit
+    pub fn memo_text(self, text: &str) -> Result<Self, MemoTextError> {
+        Ok(self.memo(SignedCommandMemo::try_from_text(text)?))
+    }
+
+    /// Set the global slot which this command is valid until
+    pub fn valid_until<T: Into<GlobalSlotNumber>>(self, valid_until: T) -> Self {
+        Self {
+            valid_until: valid_until.into(),
+            ..self
+        }
+    }
+
+    /// Same as [SignedTransferCommandBuilder::fee] but sets the fee in place, for
+    /// conditional construction without reassigning the builder
+    pub fn set_fee<T: Into<Amount>>(&mut self, fee: T) -> &mut Self {
+        self.fee = fee.into();
+        self
+    }
+
+    /// Same as [SignedTransferCommandBuilder::transfer_token] but sets the token in place
+    pub fn set_transfer_token<T: Into<TokenId>>(&mut self, transfer_token: T) -> &mut Self {
+        self.transfer_token = transfer_token.into();
+        self
+    }
+
+    /// Same as [SignedTransferCommandBuilder::fee_token] but sets the token in place
+    pub fn set_fee_token<T: Into<TokenId>>(&mut self, fee_token: T) -> &mut Self {
+        self.fee_token = fee_token.into();
+        self
+    }
+
+    /// Same as [SignedTransferCommandBuilder::fee_payer] but sets the fee payer in place
+    pub fn set_fee_payer(&mut self, fee_payer_pk: CompressedPubKey) -> &mut Self {
+        self.fee_payer_pk = fee_payer_pk;
+        self
+    }
+
+    /// Same as [SignedTransferCommandBuilder::memo] but sets the memo in place
+    pub fn set_memo(&mut self, memo: SignedCommandMemo) -> &mut Self {
+        self.memo = memo;
+        self
+    }
+
+    /// Same as [SignedTransferCommandBuilder::valid_until] but sets the slot in place
+    pub fn set_valid_until<T: Into<GlobalSlotNumber>>(&mut self, valid_until: T) -> &mut Self {
+        self.valid_until = valid_until.into();
+        self
+    }
+
+    /// Make [SignedTransferCommandBuilder::try_build] reject payments from an account to
+    /// itself. These are valid on chain but usually a user error, so wallets can opt in to
+    /// this check
+    pub fn disallow_self_payment(self) -> Self {
+        Self {
+            disallow_self_payment: true,
+            ..self
+        }
+    }
+
+    /// Make [SignedTransferCommandBuilder::try_build] reject payments of amount 0.
+    /// These are valid on chain but usually a mistake, so zero is allowed unless this is set
+    pub fn require_nonzero_amount(self) -> Self {
+        Self {
+            require_nonzero_amount: true,
+            ..self
+        }
+    }
+
+    /// Make [SignedTransferCommandBuilder::try_build] reject amounts and fees above
+    /// [Amount::MAX_SUPPLY], which are almost certainly given in the wrong unit.
+    /// Opt-in as the bound is a practical one rather than a protocol rule
+    pub fn require_within_supply(self) -> Self {
+        Self {
+            require_within_supply: true,
+            ..self
+        }
+    }
+
+    /// Make [SignedTransferCommandBuilder::try_build] reject commands already expired at the
+    /// current slot, which the daemon would drop. A `valid_until` of
+    /// [GlobalSlotNumber::forever] never expires, see [SignedCommandPayloadCommon::is_expired]
+    pub fn reject_expired<T: Into<GlobalSlotNumber>>(self, current_slot: T) -> Self {
+        Self {
+            reject_expired_at: Some(current_slot.into()),
+            ..self
+        }
+    }
+
+    /// Same as [SignedTransferCommandBuilder::build] but applies the checks enabled on the builder,
+    /// and always rejects token combinations the daemon drops, see
+    /// [SignedCommandPayload::check_tokens]
+    pub fn try_build(self) -> Result<SignedCommandPayload, TransferBuildError> {
+        if self.disallow_self_payment && self.from == self.to {
+            return Err(TransferBuildError::SelfPayment(self.from));
+        }
+        if self.require_nonzero_amount && self.amount.0 == 0 {
+            return Err(TransferBuildError::ZeroAmount);
+        }
+        if self.require_within_supply {
+            for amount in [self.amount, self.fee] {
+                if !amount.within_supply() {
+                    return Err(TransferBuildError::ExceedsSupply(amount));
+                }
+            }
+        }
+        let reject_expired_at = self.reject_expired_at;
+        let payload = self.build();
+        if let Some(current_slot) = reject_expired_at {
+            if payload.common.is_expired(current_slot) {
+                return Err(TransferBuildError::Expired {
+                    valid_until: payload.common.valid_until,
+                    current_slot,
+                });
+            }
+        }
+        payload.check_tokens()?;
+        Ok(payload)
+    }
+
+    /// Sign the transaction and produce a UserCommand with the signature fields filled.
+    /// Opt-in checks such as [SignedTransferCommandBuilder::require_nonzero_amount] are only
+    /// applied by [SignedTransferCommandBuilder::try_build]
+    pub fn build(self) -> SignedCommandPayload {
+        SignedCommandPayload {
+            common: SignedCommandPayloadCommon {
+                fee: self.fee,
+                fee_token: self.fee_token,
+                memo: self.memo,
+                fee_payer_pk: self.fee_payer_pk,
+                nonce: self.nonce,
+                valid_until: self.valid_until,
+            },
+            body: SignedCommandPayloadBody::PaymentPayload(PaymentPayload::new(
+                self.from,
+                self.to,
+                self.amount,
+                self.transfer_token,
+            )),
+        }
+    }
+
+    /// Build the payload and sign it with the given keypair and network ID,
+    /// see [SignedCommand::from_payload]
+    pub fn build_and_sign(self, keypair: Keypair, network: NetworkId) -> SignedCommand {
+        SignedCommand::from_payload(self.build(), keypair, network)
+    }
+}
+
+/// Error type for building a command from addresses
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum AddressError {
+    /// The source address could not be parsed
+    #[error("Invalid source address {0}: {1}")]
+    InvalidSource(String, String),
+    /// The receiver address could not be parsed
+    #[error("Invalid receiver address {0}: {1}")]
+    InvalidReceiver(String, String),
+}
+
+/// Error returned by [SignedTransferCommandBuilder::try_build]
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TransferBuildError {
+    /// The source is also the receiver and
+    /// [SignedTransferCommandBuilder::disallow_self_payment] was set
+    #[error("Payment source and receiver are the same account {}", .0.into_address())]
+    SelfPayment(CompressedPubKey),
+    /// The amount is 0 and [SignedTransferCommandBuilder::require_nonzero_amount] was set
+    #[error("Payment amount must not be zero")]
+    ZeroAmount,
+    /// The amount or fee is above [Amount::MAX_SUPPLY] and
+    /// [SignedTransferCommandBuilder::require_within_supply] was set
+    #[error("{0} exceeds the total supply")]
+    ExceedsSupply(Amount),
+    /// The command is expired and [SignedTransferCommandBuilder::reject_expired] was set
+    #[error("Command is valid until slot {}, current slot is {}", valid_until.0, current_slot.0)]
+    Expired {
+        /// Last slot the command is valid at
+        valid_until: GlobalSlotNumber,
+        /// Slot given to [SignedTransferCommandBuilder::reject_expired]
+        current_slot: GlobalSlotNumber,
+    },
+    /// The tokens are not accepted by the daemon
+    #[error(transparent)]
+    Tokens(#[from] TokenError),
+}
+
+/// A builder for UserCommands to delegate stake
+pub struct SignedDelegationCommandBuilder {
+    delegator: CompressedPubKey,
+    new_delegate: CompressedPubKey,
+    fee_token: TokenId,
+    fee: Amount,
+    nonce: AccountNonce,
+    memo: SignedCommandMemo,
+    fee_payer_pk: CompressedPubKey,
+    valid_until: GlobalSlotNumber,
+}
+
+impl SignedDelegationCommandBuilder {
+    /// All required fields must be defined initially
+    pub fn new<T: Into<Amount>, TT: Into<AccountNonce>>(
+        delegator: CompressedPubKey,
+        new_delegate: CompressedPubKey,
+        fee: T,
+        nonce: TT,
+    ) -> Self {
+        Self {
+            delegator: delegator.clone(),
+            new_delegate,
+            fee_token: TokenId::default_token(),
+            fee: fee.into(),
+            nonce: nonce.into(),
+            fee_payer_pk: delegator,
+            memo: SignedCommandMemo::default(),
+            valid_until: GlobalSlotNumber::MAX,
+        }
+    }
+
+    /// Set the fee token to pay the block producer
+    pub fn fee_token<T: Into<TokenId>>(self, fee_token: T) -> Self {
+        Self {
+            fee_token: fee_token.into(),
+            ..self
+        }
+    }
+
+    /// Set the fee payer to something other than the delegator
+    pub fn fee_payer(self, fee_payer_pk: CompressedPubKey) -> Self {
+        Self {
+            fee_payer_pk,
+            ..self
+        }
+    }
+
+    /// Set a non-empty memo for the command
+    pub fn memo(self, memo: SignedCommandMemo) -> Self {
+        Self { memo, ..self }
+    }
+
+    /// Set a memo holding the given bytes, failing if they do not fit
+    pub fn memo_bytes(self, bytes: &[u8]) -> Result<Self, MemoTextError> {
+        Ok(self.memo(SignedCommandMemo::try_from_bytes(bytes)?))
+    }
+
+    /// Set a memo holding the given text, failing if it does not fit
+    pub fn memo_text(self, text: &str) -> Result<Self, MemoTextError> {
+        Ok(self.memo(SignedCommandMemo::try_from_text(text)?))
+    }
+
     /// Set the global slot which this command is valid until
     pub fn valid_until<T: Into<GlobalSlotNumber>>(self, valid_until: T) -> Self {
         Self {
@@ -86,7 +403,32 @@ impl SignedTransferCommandBuilder {
         }
     }
 
-    /// Sign the transaction and produce a UserCommand with the signature fields filled
+    /// Same as [SignedDelegationCommandBuilder::fee_token] but sets the token in place, for
+    /// conditional construction without reassigning the builder
+    pub fn set_fee_token<T: Into<TokenId>>(&mut self, fee_token: T) -> &mut Self {
+        self.fee_token = fee_token.into();
+        self
+    }
+
+    /// Same as [SignedDelegationCommandBuilder::fee_payer] but sets the fee payer in place
+    pub fn set_fee_payer(&mut self, fee_payer_pk: CompressedPubKey) -> &mut Self {
+        self.fee_payer_pk = fee_payer_pk;
+        self
+    }
+
+    /// Same as [SignedDelegationCommandBuilder::memo] but sets the memo in place
+    pub fn set_memo(&mut self, memo: SignedCommandMemo) -> &mut Self {
+        self.memo = memo;
+        self
+    }
+
+    /// Same as [SignedDelegationCommandBuilder::valid_until] but sets the slot in place
+    pub fn set_valid_until<T: Into<GlobalSlotNumber>>(&mut self, valid_until: T) -> &mut Self {
+        self.valid_until = valid_until.into();
+        self
+    }
+
+    /// Build the payload of a stake delegation command, ready to be signed
     pub fn build(self) -> SignedCommandPayload {
         SignedCommandPayload {
             common: SignedCommandPayloadCommon {
@@ -97,12 +439,379 @@ impl SignedTransferCommandBuilder {
                 nonce: self.nonce,
                 valid_until: self.valid_until,
             },
-            body: SignedCommandPayloadBody::PaymentPayload(PaymentPayload {
-                amount: self.amount,
-                receiver_pk: self.to,
-                source_pk: self.from,
-                token_id: self.transfer_token,
+            body: SignedCommandPayloadBody::StakeDelegation(StakeDelegation::SetDelegate {
+                delegator: self.delegator,
+                new_delegate: self.new_delegate,
             }),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_commands::signed_command::test_util::payment_test_1;
+
+    #[test]
+    fn transfer_builder_from_addresses() -> anyhow::Result<()> {
+        let source = "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV";
+        let receiver = "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt";
+        let payload = SignedTransferCommandBuilder::from_addresses(
+            source,
+            receiver,
+            1729000000000,
+            2000000000,
+            16,
+        )?
+        .build();
+        let expected = SignedTransferCommandBuilder::new(
+            CompressedPubKey::from_address(source).unwrap(),
+            CompressedPubKey::from_address(receiver).unwrap(),
+            1729000000000,
+            2000000000,
+            16,
+        )
+        .build();
+        assert_eq!(payload, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn transfer_builder_from_addresses_malformed_receiver() {
+        let source = "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV";
+        // Last character altered, the checksum no longer matches
+        let receiver = "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zu";
+        let err = SignedTransferCommandBuilder::from_addresses(source, receiver, 1, 1, 0)
+            .err()
+            .expect("malformed receiver must be rejected");
+        assert!(matches!(err, AddressError::InvalidReceiver(address, _) if address == receiver));
+    }
+
+    #[test]
+    fn transfer_builder_from_addresses_rejects_identity() {
+        let source = "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV";
+        // Well formed address of x = 0
+        let identity = CompressedPubKey::empty().into_address();
+        let err = SignedTransferCommandBuilder::from_addresses(source, &identity, 1, 1, 0)
+            .err()
+            .expect("the identity must be rejected");
+        assert!(matches!(err, AddressError::InvalidReceiver(address, _) if address == identity));
+        let err = SignedTransferCommandBuilder::from_addresses(&identity, source, 1, 1, 0)
+            .err()
+            .expect("the identity must be rejected");
+        assert!(matches!(err, AddressError::InvalidSource(address, _) if address == identity));
+    }
+
+    #[test]
+    fn disallow_self_payment() {
+        let source = "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV";
+        let receiver = "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt";
+
+        // Permissive by default
+        let payload = SignedTransferCommandBuilder::from_addresses(source, source, 1, 1, 0)
+            .unwrap()
+            .build();
+        match payload.body {
+            SignedCommandPayloadBody::PaymentPayload(p) => assert!(p.is_self_payment()),
+            _ => panic!("Expected a payment body"),
+        }
+
+        let err = SignedTransferCommandBuilder::from_addresses(source, source, 1, 1, 0)
+            .unwrap()
+            .disallow_self_payment()
+            .try_build()
+            .err()
+            .expect("self payment must be rejected");
+        assert!(matches!(err, TransferBuildError::SelfPayment(pk) if pk.into_address() == source));
+
+        // The check runs in try_build, after all the other setters
+        let err = SignedTransferCommandBuilder::from_addresses(source, source, 1, 1, 0)
+            .unwrap()
+            .disallow_self_payment()
+            .fee(2)
+            .require_nonzero_amount()
+            .try_build()
+            .err()
+            .expect("self payment must be rejected");
+        assert!(matches!(err, TransferBuildError::SelfPayment(_)));
+
+        let payload = SignedTransferCommandBuilder::from_addresses(source, receiver, 1, 1, 0)
+            .unwrap()
+            .disallow_self_payment()
+            .try_build()
+            .unwrap();
+        match payload.body {
+            SignedCommandPayloadBody::PaymentPayload(p) => assert!(!p.is_self_payment()),
+            _ => panic!("Expected a payment body"),
+        }
+    }
+
+    #[test]
+    fn try_build_checks_tokens() {
+        let source = "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV";
+        let receiver = "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt";
+
+        // Transferring another token while paying fees in MINA is allowed
+        let payload = SignedTransferCommandBuilder::from_addresses(source, receiver, 1, 1, 0)
+            .unwrap()
+            .transfer_token(2)
+            .try_build()
+            .unwrap();
+        assert_eq!(payload.common.fee_token, TokenId::default_token());
+
+        let err = SignedTransferCommandBuilder::from_addresses(source, receiver, 1, 1, 0)
+            .unwrap()
+            .transfer_token(2)
+            .fee_token(2)
+            .try_build()
+            .err()
+            .expect("non default fee token must be rejected");
+        assert_eq!(
+            err,
+            TransferBuildError::Tokens(TokenError::NonDefaultFeeToken(TokenId(2)))
+        );
+
+        let err = SignedTransferCommandBuilder::from_addresses(source, receiver, 1, 1, 0)
+            .unwrap()
+            .transfer_token(0)
+            .try_build()
+            .err()
+            .expect("invalid token must be rejected");
+        assert_eq!(
+            err,
+            TransferBuildError::Tokens(TokenError::InvalidPaymentToken)
+        );
+    }
+
+    #[test]
+    fn builder_in_place_setters() -> Result<(), MemoTextError> {
+        let source = "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV";
+        let receiver = "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt";
+        let fee_payer = CompressedPubKey::from_address(
+            "B62qoqiAgERjCjXhofXiD7cMLJSKD8hE8ZtMh4jX5MPNgKB4CFxxm1N",
+        )
+        .unwrap();
+
+        for expiry in [None, Some(271828u32)] {
+            let mut builder =
+                SignedTransferCommandBuilder::from_addresses(source, receiver, 1729, 1, 16)
+                    .unwrap();
+            if let Some(slot) = expiry {
+                builder.set_valid_until(slot);
+            }
+            builder
+                .set_fee(2000000000)
+                .set_fee_payer(fee_payer.clone())
+                .set_memo(SignedCommandMemo::try_from_text("Hello Mina!")?);
+
+            let mut expected = SignedTransferCommandBuilder::from_addresses(
+                source, receiver, 1729, 2000000000, 16,
+            )
+            .unwrap()
+            .fee_payer(fee_payer.clone())
+            .memo_text("Hello Mina!")?;
+            if let Some(slot) = expiry {
+                expected = expected.valid_until(slot);
+            }
+            assert_eq!(builder.build(), expected.build());
+        }
+
+        let delegator = CompressedPubKey::from_address(source).unwrap();
+        let delegate = CompressedPubKey::from_address(receiver).unwrap();
+        let mut builder =
+            SignedDelegationCommandBuilder::new(delegator.clone(), delegate.clone(), 1, 0);
+        builder.set_valid_until(10).set_fee_payer(fee_payer.clone());
+        assert_eq!(
+            builder.build(),
+            SignedDelegationCommandBuilder::new(delegator, delegate, 1, 0)
+                .valid_until(10)
+                .fee_payer(fee_payer)
+                .build()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn require_within_supply() {
+        let source = "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV";
+        let receiver = "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt";
+        let builder = |amount: Amount, fee: Amount| {
+            SignedTransferCommandBuilder::from_addresses(source, receiver, amount, fee, 0).unwrap()
+        };
+        let above = Amount(Amount::MAX_SUPPLY.0 + 1);
+
+        // Permissive by default
+        assert!(builder(above, Amount(1)).try_build().is_ok());
+
+        assert!(builder(Amount::MAX_SUPPLY, Amount(1))
+            .require_within_supply()
+            .try_build()
+            .is_ok());
+        assert_eq!(
+            builder(above, Amount(1))
+                .require_within_supply()
+                .try_build()
+                .err()
+                .expect("amount above supply must be rejected"),
+            TransferBuildError::ExceedsSupply(above)
+        );
+        assert_eq!(
+            builder(Amount(1), above)
+                .require_within_supply()
+                .try_build()
+                .err()
+                .expect("fee above supply must be rejected"),
+            TransferBuildError::ExceedsSupply(above)
+        );
+    }
+
+    #[test]
+    fn reject_expired() {
+        let source = "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV";
+        let receiver = "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt";
+        let builder =
+            || SignedTransferCommandBuilder::from_addresses(source, receiver, 1, 1, 0).unwrap();
+
+        // Permissive by default
+        assert!(builder().valid_until(10).try_build().is_ok());
+
+        assert_eq!(
+            builder()
+                .valid_until(10)
+                .reject_expired(11)
+                .try_build()
+                .err()
+                .expect("expired command must be rejected"),
+            TransferBuildError::Expired {
+                valid_until: GlobalSlotNumber(10),
+                current_slot: GlobalSlotNumber(11),
+            }
+        );
+        // Still valid at its last slot
+        assert!(builder()
+            .valid_until(10)
+            .reject_expired(10)
+            .try_build()
+            .is_ok());
+        // The default valid_until is forever
+        assert!(builder().reject_expired(u32::MAX).try_build().is_ok());
+    }
+
+    #[test]
+    fn require_nonzero_amount() {
+        let source = "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV";
+        let receiver = "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt";
+
+        // Permissive by default
+        let payload = SignedTransferCommandBuilder::from_addresses(source, receiver, 0, 1, 0)
+            .unwrap()
+            .try_build()
+            .unwrap();
+        match payload.body {
+            SignedCommandPayloadBody::PaymentPayload(p) => assert_eq!(p.amount(), Amount(0)),
+            _ => panic!("Expected a payment body"),
+        }
+
+        let err = SignedTransferCommandBuilder::from_addresses(source, receiver, 0, 1, 0)
+            .unwrap()
+            .require_nonzero_amount()
+            .try_build()
+            .err()
+            .expect("zero amount must be rejected");
+        assert_eq!(err, TransferBuildError::ZeroAmount);
+
+        let payload = SignedTransferCommandBuilder::from_addresses(source, receiver, 1, 1, 0)
+            .unwrap()
+            .require_nonzero_amount()
+            .try_build()
+            .unwrap();
+        match payload.body {
+            SignedCommandPayloadBody::PaymentPayload(p) => assert_eq!(p.amount(), Amount(1)),
+            _ => panic!("Expected a payment body"),
+        }
+    }
+
+    #[test]
+    fn rebuild_from_command_with_new_fee() {
+        let (payload, kp) = payment_test_1();
+        let original = SignedCommand::from_payload(payload, kp.clone(), NetworkId::TESTNET);
+
+        let bumped = SignedTransferCommandBuilder::from_command(&original)
+            .expect("command is a payment")
+            .fee(3000000000)
+            .build_and_sign(kp, NetworkId::TESTNET);
+        assert_eq!(bumped.fee(), Amount(3000000000));
+        assert_eq!(bumped.payload.body, original.payload.body);
+        assert_eq!(bumped.nonce(), original.nonce());
+        assert_eq!(bumped.valid_until(), original.valid_until());
+        assert_eq!(bumped.memo(), original.memo());
+        assert_ne!(bumped.signature, original.signature);
+        assert!(bumped
+            .check_network(NetworkId::TESTNET, Default::default())
+            .is_ok());
+
+        // Repopulating without changes reproduces the payload
+        let rebuilt = SignedTransferCommandBuilder::from_command(&original)
+            .unwrap()
+            .build();
+        assert_eq!(rebuilt, original.payload);
+
+        let delegation = SignedCommand {
+            payload: SignedDelegationCommandBuilder::new(
+                original.signer.clone(),
+                original.signer.clone(),
+                1,
+                0,
+            )
+            .build(),
+            ..original
+        };
+        assert!(SignedTransferCommandBuilder::from_command(&delegation).is_none());
+    }
+
+    #[test]
+    fn builder_memo_setters() -> Result<(), MemoTextError> {
+        let source = CompressedPubKey::from_address(
+            "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+        )
+        .unwrap();
+        let receiver = CompressedPubKey::from_address(
+            "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+        )
+        .unwrap();
+        let builder =
+            || SignedTransferCommandBuilder::new(source.clone(), receiver.clone(), 1, 1, 0);
+
+        let payload = builder().memo_text("Hello Mina!")?.build();
+        assert_eq!(
+            payload.common.memo,
+            SignedCommandMemo::try_from_text("Hello Mina!")?
+        );
+
+        let bytes = [0xff, 0x00, 0x01];
+        let payload = builder().memo_bytes(&bytes)?.build();
+        assert_eq!(
+            payload.common.memo,
+            SignedCommandMemo::try_from_bytes(&bytes)?
+        );
+        assert_eq!(payload.common.memo.as_text(), None);
+
+        let payload = SignedDelegationCommandBuilder::new(source.clone(), receiver.clone(), 1, 0)
+            .memo_text("Hello Mina!")?
+            .build();
+        assert_eq!(
+            payload.common.memo.as_text().as_deref(),
+            Some("Hello Mina!")
+        );
+
+        assert_eq!(
+            builder().memo_text(&"a".repeat(33)).err(),
+            Some(MemoTextError::TooLong(33))
+        );
+        assert_eq!(
+            builder().memo_bytes(&[0; 33]).err(),
+            Some(MemoTextError::TooLong(33))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per thread cache of signer contexts, and [SigningContext] for callers managing their own
+//!
+//! Creating a signer context derives the hasher state for the network's domain string,
+//! which costs a full permutation. Contexts are reused across calls instead, which gives
+//! the same signatures since each sign or verify call restarts from that derived state.
+
+use super::{SignatureScheme, SignedCommand, SignedCommandPayload};
+use crate::verifiable::Verifiable;
+use proof_systems::mina_signer::{self, Keypair, NetworkId, Signer};
+use std::cell::RefCell;
+use std::thread::LocalKey;
+
+type SignerContext = RefCell<Box<dyn Signer<SignedCommandPayload>>>;
+
+thread_local! {
+    static LEGACY_TESTNET: SignerContext =
+        RefCell::new(Box::new(mina_signer::create_legacy::<SignedCommandPayload>(NetworkId::TESTNET)));
+    static LEGACY_MAINNET: SignerContext =
+        RefCell::new(Box::new(mina_signer::create_legacy::<SignedCommandPayload>(NetworkId::MAINNET)));
+    static KIMCHI_TESTNET: SignerContext =
+        RefCell::new(Box::new(mina_signer::create_kimchi::<SignedCommandPayload>(NetworkId::TESTNET)));
+    static KIMCHI_MAINNET: SignerContext =
+        RefCell::new(Box::new(mina_signer::create_kimchi::<SignedCommandPayload>(NetworkId::MAINNET)));
+}
+
+/// Runs f with the cached signer context of the current thread for the network and scheme
+pub(crate) fn with_signer_context<T>(
+    network: &NetworkId,
+    scheme: SignatureScheme,
+    f: impl FnOnce(&mut dyn Signer<SignedCommandPayload>) -> T,
+) -> T {
+    let ctx: &'static LocalKey<SignerContext> = match (scheme, network) {
+        (SignatureScheme::Legacy, NetworkId::TESTNET) => &LEGACY_TESTNET,
+        (SignatureScheme::Legacy, NetworkId::MAINNET) => &LEGACY_MAINNET,
+        (SignatureScheme::Kimchi, NetworkId::TESTNET) => &KIMCHI_TESTNET,
+        (SignatureScheme::Kimchi, NetworkId::MAINNET) => &KIMCHI_MAINNET,
+    };
+    ctx.with(|ctx| f(&mut **ctx.borrow_mut()))
+}
+
+/// A signer context for one network and scheme, owned by the caller rather than cached
+/// per thread. It is [Send], so that a signing service can keep a pool of them and hand
+/// them out to worker threads
+pub struct SigningContext {
+    network: NetworkId,
+    scheme: SignatureScheme,
+    signer: Box<dyn Signer<SignedCommandPayload> + Send>,
+}
+
+impl SigningContext {
+    /// Create a context for the network with [SignatureScheme::DEFAULT]
+    pub fn new(network: NetworkId) -> Self {
+        Self::with_scheme(network, SignatureScheme::DEFAULT)
+    }
+
+    /// Create a context for the network and scheme
+    pub fn with_scheme(network: NetworkId, scheme: SignatureScheme) -> Self {
+        let signer: Box<dyn Signer<SignedCommandPayload> + Send> = match scheme {
+            SignatureScheme::Legacy => Box::new(
+                mina_signer::create_legacy::<SignedCommandPayload>(network.clone()),
+            ),
+            SignatureScheme::Kimchi => Box::new(
+                mina_signer::create_kimchi::<SignedCommandPayload>(network.clone()),
+            ),
+        };
+        Self {
+            network,
+            scheme,
+            signer,
+        }
+    }
+
+    /// The network signatures are made for
+    pub fn network(&self) -> &NetworkId {
+        &self.network
+    }
+
+    /// The scheme signatures are made with
+    pub fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+
+    /// Sign the payload, same as [SignedCommand::from_payload] with this context's network
+    /// and scheme
+    pub fn sign(&mut self, payload: SignedCommandPayload, keypair: &Keypair) -> SignedCommand {
+        SignedCommand::sign_with_context(payload, keypair, &mut *self.signer)
+    }
+
+    /// Verify the signature of the command for this context's network and scheme
+    pub fn verify(&mut self, cmd: &SignedCommand) -> bool {
+        cmd.verify(&mut *self.signer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers::AccountNonce;
+    use crate::user_commands::signed_command::test_util::payment_test_1;
+
+    #[test]
+    fn signing_context_matches_from_payload() {
+        fn assert_send<T: Send>() {}
+        assert_send::<SigningContext>();
+
+        let (mut payload, kp) = payment_test_1();
+        let mut mainnet = SigningContext::new(NetworkId::MAINNET);
+        let mut testnet = SigningContext::new(NetworkId::TESTNET);
+        for nonce in 0..3 {
+            payload.common.nonce = AccountNonce(nonce);
+            let cmd = mainnet.sign(payload.clone(), &kp);
+            assert_eq!(
+                cmd,
+                SignedCommand::from_payload(payload, kp.clone(), NetworkId::MAINNET)
+            );
+            assert!(mainnet.verify(&cmd));
+            assert!(!testnet.verify(&cmd));
+        }
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Direction of a signed command relative to an account, for transaction histories
+
+use super::{SignedCommand, SignedCommandPayloadBody, StakeDelegation};
+use proof_systems::mina_signer::CompressedPubKey;
+
+/// How a signed command relates to an account, see [SignedCommand::direction]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Direction {
+    /// Payment received by the account
+    Incoming,
+    /// Payment sent by the account, or whose fee it pays
+    Outgoing,
+    /// Payment whose source and receiver are both the account
+    SelfTransfer,
+    /// Stake delegation in which the account is the delegator, the new delegate or the
+    /// fee payer
+    Delegation,
+    /// Command that does not involve the account
+    Unrelated,
+}
+
+impl SignedCommand {
+    /// Direction of the command relative to the account of `addr`. A payment is outgoing
+    /// for its source and its fee payer, and incoming for its receiver
+    pub fn direction(&self, addr: &CompressedPubKey) -> Direction {
+        let fee_payer = &self.payload.common.fee_payer_pk == addr;
+        match &self.payload.body {
+            SignedCommandPayloadBody::PaymentPayload(pp) => {
+                let source = fee_payer || &pp.source_pk == addr;
+                let receiver = &pp.receiver_pk == addr;
+                match (source, receiver) {
+                    (true, true) => Direction::SelfTransfer,
+                    (true, false) => Direction::Outgoing,
+                    (false, true) => Direction::Incoming,
+                    (false, false) => Direction::Unrelated,
+                }
+            }
+            SignedCommandPayloadBody::StakeDelegation(StakeDelegation::SetDelegate {
+                delegator,
+                new_delegate,
+            }) => {
+                if fee_payer || delegator == addr || new_delegate == addr {
+                    Direction::Delegation
+                } else {
+                    Direction::Unrelated
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_commands::signed_command::builder::{
+        SignedDelegationCommandBuilder, SignedTransferCommandBuilder,
+    };
+    use crate::user_commands::signed_command::{
+        NetworkSignedCommand, SignatureScheme, SignedCommandPayload,
+    };
+    use proof_systems::mina_signer::{Keypair, NetworkId};
+
+    fn sign(payload: SignedCommandPayload) -> SignedCommand {
+        let keypair =
+            Keypair::from_hex("164244176fddb5d769b7de2027469d027ad428fadcc0c02396e6280142efb718")
+                .unwrap();
+        NetworkSignedCommand::from_payload(
+            payload,
+            keypair,
+            NetworkId::TESTNET,
+            SignatureScheme::Legacy,
+        )
+        .command
+    }
+
+    #[test]
+    fn direction_relative_to_address() {
+        let [alice, bob, carol] = [
+            "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+            "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+            "B62qns9cPvDwckhJXHpWZZ8b8T8oUgoF4Enpax5zNVBYYMtQwHf4Cmp",
+        ]
+        .map(|address| CompressedPubKey::from_address(address).unwrap());
+
+        let payment =
+            sign(SignedTransferCommandBuilder::new(alice.clone(), bob.clone(), 1, 1, 0).build());
+        assert_eq!(payment.direction(&alice), Direction::Outgoing);
+        assert_eq!(payment.direction(&bob), Direction::Incoming);
+        assert_eq!(payment.direction(&carol), Direction::Unrelated);
+
+        let self_transfer =
+            sign(SignedTransferCommandBuilder::new(alice.clone(), alice.clone(), 1, 1, 0).build());
+        assert_eq!(self_transfer.direction(&alice), Direction::SelfTransfer);
+        assert_eq!(self_transfer.direction(&bob), Direction::Unrelated);
+
+        // The fee payer pays for the payment even when it is not the source
+        let mut sponsored =
+            SignedTransferCommandBuilder::new(alice.clone(), bob.clone(), 1, 1, 0).build();
+        sponsored.common.fee_payer_pk = carol.clone();
+        let sponsored = sign(sponsored);
+        assert_eq!(sponsored.direction(&carol), Direction::Outgoing);
+        assert_eq!(sponsored.direction(&alice), Direction::Outgoing);
+        assert_eq!(sponsored.direction(&bob), Direction::Incoming);
+
+        let delegation =
+            sign(SignedDelegationCommandBuilder::new(alice.clone(), bob.clone(), 1, 0).build());
+        assert_eq!(delegation.direction(&alice), Direction::Delegation);
+        assert_eq!(delegation.direction(&bob), Direction::Delegation);
+        assert_eq!(delegation.direction(&carol), Direction::Unrelated);
+    }
+}
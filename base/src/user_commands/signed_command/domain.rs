@@ -0,0 +1,189 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Signature domains for networks other than mainnet and testnet
+//!
+//! The domain string initializes the hasher a command is signed with, so a signature only
+//! verifies under the domain it was made for. Private devnets and forks can pick their own
+//! domain to keep their commands from being replayed on the public networks.
+
+use super::{debug_assert_no_zero_keys, SignatureScheme, SignedCommand, SignedCommandPayload};
+use crate::public_key::CompressedPubKeyExt;
+use proof_systems::mina_hasher::{DomainParameter, Hashable, ROInput};
+use proof_systems::mina_signer::{self, Keypair, NetworkId, Signer};
+use thiserror::Error;
+
+/// Maximum length in bytes of a domain string, which is packed into a single field element
+pub const MAX_DOMAIN_LEN: usize = 20;
+
+/// Error returned by [SignatureDomain::custom]
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum SignatureDomainError {
+    /// The domain string is longer than [MAX_DOMAIN_LEN] bytes
+    #[error("Domain string of {len} bytes exceeds the maximum of {max}", len = .0, max = MAX_DOMAIN_LEN)]
+    TooLong(usize),
+}
+
+/// Domain a command is signed for, the network id together with the domain string
+#[derive(Clone, Debug)]
+pub struct SignatureDomain {
+    network: NetworkId,
+    domain: String,
+}
+
+impl SignatureDomain {
+    /// A custom domain string. The network id is still mixed into the signature nonce,
+    /// so forks of a network should keep its id
+    pub fn custom(
+        network: NetworkId,
+        domain: impl Into<String>,
+    ) -> Result<Self, SignatureDomainError> {
+        let domain = domain.into();
+        if domain.len() > MAX_DOMAIN_LEN {
+            return Err(SignatureDomainError::TooLong(domain.len()));
+        }
+        Ok(Self { network, domain })
+    }
+
+    /// The domain string
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+}
+
+impl From<NetworkId> for SignatureDomain {
+    /// The domain commands for the network are signed with by default
+    fn from(network: NetworkId) -> Self {
+        let domain = SignedCommandPayload::domain_string(network.clone())
+            .expect("known networks have a domain string");
+        Self { network, domain }
+    }
+}
+
+impl DomainParameter for SignatureDomain {
+    fn into_bytes(self) -> Vec<u8> {
+        self.network.into_bytes()
+    }
+}
+
+/// A payload hashed with the domain string of a [SignatureDomain]
+#[derive(Clone)]
+struct DomainPayload(SignedCommandPayload);
+
+impl Hashable for DomainPayload {
+    type D = SignatureDomain;
+
+    fn to_roinput(&self) -> ROInput {
+        self.0.to_roinput()
+    }
+
+    fn domain_string(domain: SignatureDomain) -> Option<String> {
+        Some(domain.domain)
+    }
+}
+
+fn signer_context(
+    domain: &SignatureDomain,
+    scheme: SignatureScheme,
+) -> Box<dyn Signer<DomainPayload>> {
+    match scheme {
+        SignatureScheme::Legacy => {
+            Box::new(mina_signer::create_legacy::<DomainPayload>(domain.clone()))
+        }
+        SignatureScheme::Kimchi => {
+            Box::new(mina_signer::create_kimchi::<DomainPayload>(domain.clone()))
+        }
+    }
+}
+
+impl SignedCommand {
+    /// Sign a SignedCommandPayload for the given domain, which can be one of a network
+    /// other than mainnet and testnet
+    pub fn from_payload_with_domain(
+        payload: SignedCommandPayload,
+        keypair: &Keypair,
+        domain: &SignatureDomain,
+        scheme: SignatureScheme,
+    ) -> Self {
+        debug_assert_no_zero_keys(&payload);
+        let payload = DomainPayload(payload);
+        let signature = signer_context(domain, scheme).sign(keypair, &payload);
+        SignedCommand {
+            payload: payload.0,
+            signer: keypair.public.into_compressed(),
+            signature,
+        }
+    }
+
+    /// Verify the command was signed for the given domain with the given scheme
+    pub fn verify_with_domain(&self, domain: &SignatureDomain, scheme: SignatureScheme) -> bool {
+        let signer_uncompressed = match self.signer.to_pub_key() {
+            Ok(signer) => signer,
+            Err(_) => return false,
+        };
+        signer_context(domain, scheme).verify(
+            &self.signature,
+            &signer_uncompressed,
+            &DomainPayload(self.payload.clone()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_commands::signed_command::test_util::payment_test_1;
+
+    #[test]
+    fn sign_and_verify_with_custom_domain() -> Result<(), SignatureDomainError> {
+        let (payload, kp) = payment_test_1();
+        let devnet = SignatureDomain::custom(NetworkId::TESTNET, "MyDevnetSignature")?;
+        let other = SignatureDomain::custom(NetworkId::TESTNET, "OtherSignature")?;
+
+        for scheme in [SignatureScheme::Legacy, SignatureScheme::Kimchi] {
+            let cmd =
+                SignedCommand::from_payload_with_domain(payload.clone(), &kp, &devnet, scheme);
+            assert!(cmd.verify_with_domain(&devnet, scheme));
+            assert!(!cmd.verify_with_domain(&other, scheme));
+            assert!(!cmd.verify_with_domain(&NetworkId::TESTNET.into(), scheme));
+            assert!(cmd.check_network(NetworkId::TESTNET, scheme).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn known_network_domains_are_the_defaults() {
+        let (payload, kp) = payment_test_1();
+        for network in [NetworkId::TESTNET, NetworkId::MAINNET] {
+            let domain = SignatureDomain::from(network.clone());
+            let cmd = SignedCommand::from_payload_with_domain(
+                payload.clone(),
+                &kp,
+                &domain,
+                SignatureScheme::Legacy,
+            );
+            assert_eq!(
+                cmd,
+                crate::user_commands::NetworkSignedCommand::from_payload(
+                    payload.clone(),
+                    kp.clone(),
+                    network,
+                    SignatureScheme::Legacy,
+                )
+                .into()
+            );
+        }
+        assert_eq!(
+            SignatureDomain::from(NetworkId::MAINNET).domain(),
+            "MinaSignatureMainnet"
+        );
+    }
+
+    #[test]
+    fn custom_domain_too_long() {
+        assert_eq!(
+            SignatureDomain::custom(NetworkId::TESTNET, "ThisDomainIsWayTooLong").err(),
+            Some(SignatureDomainError::TooLong(22))
+        );
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Default fees suggested to wallets per kind of command, see
+//! [SignedCommandPayloadBody::suggested_fee]
+
+use super::SignedCommandPayloadBody;
+use crate::constants::MINA_PRECISION;
+use crate::numbers::Amount;
+
+/// Fee suggested for each kind of command. The [Default] values are conservative, above
+/// the fees typically paid on mainnet, so that commands are included without delay
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct FeeSchedule {
+    /// Fee suggested for payments
+    pub payment: Amount,
+    /// Fee suggested for stake delegations
+    pub delegation: Amount,
+}
+
+impl FeeSchedule {
+    /// 0.1 MINA for payments and stake delegations alike
+    pub const DEFAULT: Self = Self {
+        payment: Amount(MINA_PRECISION / 10),
+        delegation: Amount(MINA_PRECISION / 10),
+    };
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl SignedCommandPayloadBody {
+    /// Fee suggested for this kind of command by [FeeSchedule::DEFAULT]
+    pub fn suggested_fee(&self) -> Amount {
+        self.suggested_fee_with(&FeeSchedule::DEFAULT)
+    }
+
+    /// Fee suggested for this kind of command by the schedule
+    pub fn suggested_fee_with(&self, schedule: &FeeSchedule) -> Amount {
+        match self {
+            Self::PaymentPayload(_) => schedule.payment,
+            Self::StakeDelegation(_) => schedule.delegation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_commands::signed_command::builder::{
+        SignedDelegationCommandBuilder, SignedTransferCommandBuilder,
+    };
+    use proof_systems::mina_signer::CompressedPubKey;
+
+    #[test]
+    fn suggested_fee_per_kind() {
+        let source = CompressedPubKey::from_address(
+            "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+        )
+        .unwrap();
+        let receiver = CompressedPubKey::from_address(
+            "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+        )
+        .unwrap();
+        let payment =
+            SignedTransferCommandBuilder::new(source.clone(), receiver.clone(), 1, 1, 0).build();
+        let delegation = SignedDelegationCommandBuilder::new(source, receiver, 1, 0).build();
+
+        assert_eq!(payment.body.suggested_fee(), Amount(100_000_000));
+        assert_eq!(delegation.body.suggested_fee(), Amount(100_000_000));
+
+        let schedule = FeeSchedule {
+            payment: Amount(10_000_000),
+            delegation: Amount(20_000_000),
+        };
+        assert_eq!(payment.body.suggested_fee_with(&schedule), schedule.payment);
+        assert_eq!(
+            delegation.body.suggested_fee_with(&schedule),
+            schedule.delegation
+        );
+    }
+}
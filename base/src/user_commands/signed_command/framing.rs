@@ -0,0 +1,116 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Length prefixed framing of signed commands, to relay them over a plain byte stream
+//!
+//! Each frame is the length of the bin_prot encoding of the command as a u32, little-endian
+//! like bin_prot integers, followed by the encoding itself.
+
+use super::SignedCommand;
+use mina_serialization_types::{v1::SignedCommandV1, BinProtSerializationType};
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+/// Largest frame length accepted by [SignedCommand::read_framed]. An encoded signed command
+/// takes a few hundred bytes, so anything longer is a corrupt or malicious stream, and is
+/// rejected before allocating for it
+pub const MAX_FRAME_LEN: u32 = 4096;
+
+/// Error type for reading or writing a framed signed command
+#[derive(Debug, Error)]
+pub enum FrameError {
+    /// Error in the underlying reader or writer
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    /// The frame length is larger than [MAX_FRAME_LEN]
+    #[error("Frame length {0} exceeds the maximum of {MAX_FRAME_LEN}")]
+    TooLong(u32),
+    /// The frame does not hold exactly one bin_prot encoded signed command
+    #[error("Invalid frame content: {0}")]
+    BinProt(#[from] bin_prot::error::Error),
+}
+
+impl SignedCommand {
+    /// Write the command as one frame: its bin_prot length as a little-endian u32, then
+    /// its bin_prot encoding
+    pub fn write_framed<W: Write>(&self, mut writer: W) -> Result<(), FrameError> {
+        let bytes = self.clone().try_into_binprot()?;
+        let len = u32::try_from(bytes.len()).unwrap_or(u32::MAX);
+        if len > MAX_FRAME_LEN {
+            return Err(FrameError::TooLong(len));
+        }
+        writer.write_all(&len.to_le_bytes())?;
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// Read one frame written by [SignedCommand::write_framed]. Frame lengths above
+    /// [MAX_FRAME_LEN] are rejected, and the frame must hold exactly one command
+    pub fn read_framed<R: Read>(mut reader: R) -> Result<Self, FrameError> {
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len);
+        if len > MAX_FRAME_LEN {
+            return Err(FrameError::TooLong(len));
+        }
+        let mut bytes = vec![0u8; len as usize];
+        reader.read_exact(&mut bytes)?;
+        let cmd: SignedCommandV1 = bin_prot::from_reader_strict(bytes.as_slice())?;
+        Ok(cmd.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numbers::AccountNonce;
+    use crate::user_commands::signed_command::test_util::payment_test_1;
+    use proof_systems::mina_signer::NetworkId;
+    use std::io::Cursor;
+
+    #[test]
+    fn framed_roundtrip() {
+        let (payload, kp) = payment_test_1();
+        let cmds: Vec<SignedCommand> = (0..3)
+            .map(|nonce| {
+                let mut payload = payload.clone();
+                payload.common.nonce = AccountNonce(nonce);
+                SignedCommand::from_payload(payload, kp.clone(), NetworkId::TESTNET)
+            })
+            .collect();
+
+        let mut pipe = Vec::new();
+        for cmd in &cmds {
+            cmd.write_framed(&mut pipe).unwrap();
+        }
+        let frame_len = u32::from_le_bytes(pipe[..4].try_into().unwrap());
+        assert_eq!(frame_len as usize, cmds[0].serialized_size());
+        assert_eq!(pipe.len(), cmds.len() * (4 + frame_len as usize));
+
+        let mut reader = Cursor::new(pipe.clone());
+        for cmd in &cmds {
+            assert_eq!(&SignedCommand::read_framed(&mut reader).unwrap(), cmd);
+        }
+        assert!(matches!(
+            SignedCommand::read_framed(&mut reader),
+            Err(FrameError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+
+        // Absurd lengths are rejected without reading the frame
+        let mut absurd = u32::MAX.to_le_bytes().to_vec();
+        absurd.extend_from_slice(&pipe[4..]);
+        assert!(matches!(
+            SignedCommand::read_framed(absurd.as_slice()),
+            Err(FrameError::TooLong(u32::MAX))
+        ));
+
+        // A frame longer than its command leaves bytes unconsumed
+        let mut padded = (frame_len + 1).to_le_bytes().to_vec();
+        padded.extend_from_slice(&pipe[4..4 + frame_len as usize]);
+        padded.push(0);
+        assert!(matches!(
+            SignedCommand::read_framed(padded.as_slice()),
+            Err(FrameError::BinProt(_))
+        ));
+    }
+}
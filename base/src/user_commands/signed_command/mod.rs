@@ -4,23 +4,81 @@
 //! Signed commands are commands that require signing with some accounts private key
 
 pub mod builder;
+mod context;
+mod direction;
+pub mod domain;
+mod fee_schedule;
+mod framing;
+pub mod tag;
+#[cfg(test)]
+pub(crate) mod test_util;
+pub mod typed;
+
+pub use context::SigningContext;
+pub use direction::Direction;
+pub use fee_schedule::FeeSchedule;
+pub use framing::{FrameError, MAX_FRAME_LEN};
+pub use tag::TransactionTag;
+pub use typed::{CommandKindError, DelegationCommand, PaymentCommand};
 
+use crate::from_graphql_json::{FromGraphQLJson, ToGraphQLJson};
+use crate::keypair::{KeypairExt, SecretKeyError};
 use crate::numbers::{AccountNonce, Amount, GlobalSlotNumber, TokenId};
+use crate::public_key::{CompressedPubKeyExt, OrdPubKey};
+use crate::signature::SignatureExt;
 use crate::user_commands::memo::SignedCommandMemo;
 use crate::user_commands::payment::PaymentPayload;
-use crate::verifiable::Verifiable;
+use crate::verifiable::{Signable, Verifiable};
 
+use ark_ff::{PrimeField, Zero};
+use blake2::{
+    digest::{Update, VariableOutput},
+    Blake2bVar,
+};
+use mina_serialization_types::{
+    json::{SignatureJson, SignedCommandJson, SignedCommandMemoJson, SignedCommandPayloadJson},
+    v1::{SignatureV1, SignedCommandV1},
+    BinProtSerializationType, JsonSerializationType,
+};
 use mina_serialization_types_macros::AutoFrom;
-use proof_systems::mina_hasher::{Hashable, ROInput};
-use proof_systems::mina_signer::{CompressedPubKey, Keypair, NetworkId, PubKey, Signature, Signer};
+use proof_systems::mina_hasher::{Fp, Hashable, ROInput};
+use proof_systems::mina_signer::{CompressedPubKey, Keypair, NetworkId, Signature, Signer};
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
+use thiserror::Error;
+use versioned::impl_from_with_proxy;
 
 const TAG_BITS: usize = 3;
-const PAYMENT_TX_TAG: [bool; TAG_BITS] = [false, false, false];
-const DELEGATION_TX_TAG: [bool; TAG_BITS] = [false, false, true];
+const PAYMENT_TX_TAG: [bool; TAG_BITS] = TransactionTag::Payment.as_bits();
+const DELEGATION_TX_TAG: [bool; TAG_BITS] = TransactionTag::StakeDelegation.as_bits();
+
+// Stake delegations are hashed as a transaction union payload that always carries the default
+// token and a zero amount, independently of the fee token
+const DELEGATION_TOKEN_ID: TokenId = TokenId::default_token();
+const DELEGATION_AMOUNT: Amount = Amount(0);
+
+// Domain separation prefix of the digest behind SignedCommand::transaction_id
+const TRANSACTION_ID_DOMAIN: &[u8] = b"MinaRsTransactionId";
+
+// Number of hex characters of the signature shown by SignedCommand::summary
+const SUMMARY_SIGNATURE_HEX_LEN: usize = 16;
+
+// Random oracle input layout shared by payments and stake delegations, see the
+// Hashable impl of SignedCommandPayload
+const ROINPUT_FIELDS: usize = 3;
+// fee, fee token, fee payer parity, nonce and valid until
+const COMMON_BITS: usize = 64 + 64 + 1 + 32 + 32;
+// source or delegator parity, receiver or delegate parity, token id, amount and token locked
+const BODY_BITS: usize = 1 + 1 + 64 + 64 + 1;
 
 /// Top level signed command type
 #[derive(Clone, Eq, PartialEq, Debug, AutoFrom)]
 #[auto_from(mina_serialization_types::staged_ledger_diff::SignedCommand)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SignedCommandJson", from = "SignedCommandJson")
+)]
 pub struct SignedCommand {
     /// The payload to sign
     pub payload: SignedCommandPayload,
@@ -30,16 +88,194 @@ pub struct SignedCommand {
     pub signature: Signature,
 }
 
+impl_from_with_proxy!(
+    SignedCommand,
+    mina_serialization_types::staged_ledger_diff::SignedCommand,
+    SignedCommandJson
+);
+
+impl BinProtSerializationType<'_> for SignedCommand {
+    type T = SignedCommandV1;
+}
+
+impl JsonSerializationType<'_> for SignedCommand {
+    type T = SignedCommandJson;
+}
+
+impl Hash for SignedCommand {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.payload.hash(state);
+        self.signer.hash_key(state);
+        self.signature.hash_signature(state);
+    }
+}
+
+impl ToGraphQLJson for SignedCommand {
+    // Shape of the UserCommand type of the graphql API, with amounts, tokens and slots as
+    // decimal strings. Delegations carry the new delegate as receiver together with the
+    // default token and a zero amount, as they are hashed
+    fn to_graphql_json(&self) -> serde_json::Value {
+        let common = &self.payload.common;
+        let (kind, source, receiver, token, amount) = match &self.payload.body {
+            SignedCommandPayloadBody::PaymentPayload(pp) => (
+                "PAYMENT",
+                pp.source_pk(),
+                pp.receiver_pk(),
+                pp.token_id().clone(),
+                pp.amount(),
+            ),
+            SignedCommandPayloadBody::StakeDelegation(StakeDelegation::SetDelegate {
+                delegator,
+                new_delegate,
+            }) => (
+                "STAKE_DELEGATION",
+                delegator,
+                new_delegate,
+                DELEGATION_TOKEN_ID,
+                DELEGATION_AMOUNT,
+            ),
+        };
+        serde_json::json!({
+            "kind": kind,
+            "isDelegation": kind == "STAKE_DELEGATION",
+            "nonce": common.nonce.0,
+            "feePayer": { "publicKey": common.fee_payer_pk.into_address() },
+            "source": { "publicKey": source.into_address() },
+            "receiver": { "publicKey": receiver.into_address() },
+            "fee": common.fee.0.to_string(),
+            "feeToken": common.fee_token.0.to_string(),
+            "amount": amount.0.to_string(),
+            "token": token.0.to_string(),
+            "validUntil": common.valid_until.0.to_string(),
+            "memo": SignedCommandMemoJson::from(common.memo.clone()),
+            "signer": { "publicKey": self.signer.into_address() },
+            "signature": SignatureJson::from(SignatureV1::from(&self.signature)),
+        })
+    }
+}
+
+// Integers of the graphql json are decimal strings, except the nonce which is a number
+fn graphql_u64(json: &serde_json::Value, member: &str) -> anyhow::Result<u64> {
+    match &json[member] {
+        serde_json::Value::Number(n) => n.as_u64(),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| anyhow::anyhow!("Expected an integer {member}"))
+}
+
+fn graphql_public_key(json: &serde_json::Value, member: &str) -> anyhow::Result<CompressedPubKey> {
+    let address = json[member]["publicKey"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Expected a public key {member}"))?;
+    Ok(CompressedPubKey::from_address_checked(address)?)
+}
+
+impl FromGraphQLJson for SignedCommand {
+    // Inverse of the ToGraphQLJson impl. The body is picked by the kind field and not by which
+    // fields are present, as the node sends delegations with the new delegate as receiver and
+    // a null or missing amount, which must not be read as a payment
+    fn from_graphql_json(json: &serde_json::Value) -> anyhow::Result<Self> {
+        let body = match json["kind"].as_str() {
+            Some("PAYMENT") => SignedCommandPayloadBody::PaymentPayload(PaymentPayload::new(
+                graphql_public_key(json, "source")?,
+                graphql_public_key(json, "receiver")?,
+                graphql_u64(json, "amount")?,
+                graphql_u64(json, "token")?,
+            )),
+            Some("STAKE_DELEGATION") => {
+                SignedCommandPayloadBody::StakeDelegation(StakeDelegation::SetDelegate {
+                    delegator: graphql_public_key(json, "source")?,
+                    new_delegate: graphql_public_key(json, "receiver")?,
+                })
+            }
+            kind => anyhow::bail!("Unsupported command kind {kind:?}"),
+        };
+        let memo = json["memo"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Expected a string memo"))?;
+        let signature = match &json["signature"] {
+            serde_json::Value::String(s) => Signature::from_base58(s)?,
+            object => Signature::from_json_object(object)?,
+        };
+        Ok(Self {
+            payload: SignedCommandPayload {
+                common: SignedCommandPayloadCommon {
+                    fee: Amount(graphql_u64(json, "fee")?),
+                    fee_token: TokenId(graphql_u64(json, "feeToken")?),
+                    fee_payer_pk: graphql_public_key(json, "feePayer")?,
+                    nonce: AccountNonce(graphql_u64(json, "nonce")?.try_into()?),
+                    valid_until: GlobalSlotNumber(graphql_u64(json, "validUntil")?.try_into()?),
+                    memo: memo.parse()?,
+                },
+                body,
+            },
+            signer: graphql_public_key(json, "signer")?,
+            signature,
+        })
+    }
+}
+
 impl SignedCommand {
-    /// Sign a SignedCommandPayload to construct a SignedCommand
+    /// Sign a SignedCommandPayload to construct a SignedCommand with [SignatureScheme::DEFAULT].
+    /// The keypair is consumed without being cloned, and the signer context for the
+    /// network is cached per thread so batch signing does not derive it for every command
     pub fn from_payload(
         payload: SignedCommandPayload,
         keypair: Keypair,
         network: NetworkId,
     ) -> Self {
-        // This should change to create_kimchi after fork
-        let mut ctx = proof_systems::mina_signer::create_legacy::<SignedCommandPayload>(network);
-        let signature = ctx.sign(&keypair, &payload);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!(
+            "sign_command",
+            network = ?network,
+            signer = %keypair.public.into_address(),
+            kind = payload.body.kind_name(),
+        )
+        .entered();
+        context::with_signer_context(&network, SignatureScheme::DEFAULT, |ctx| {
+            Self::sign_with_context(payload, &keypair, ctx)
+        })
+    }
+
+    /// Same as [SignedCommand::from_payload] but borrows the payload and keypair, so that the
+    /// same payload can be signed for several networks. Only the payload is copied, into the
+    /// returned command
+    pub fn sign_ref(payload: &SignedCommandPayload, keypair: &Keypair, network: NetworkId) -> Self {
+        context::with_signer_context(&network, SignatureScheme::DEFAULT, |ctx| {
+            Self::sign_with_context(payload.clone(), keypair, ctx)
+        })
+    }
+
+    /// Same as [SignedCommand::from_payload] with the keypair parsed from the big-endian hex
+    /// of its secret key
+    pub fn from_payload_with_hex_sk(
+        payload: SignedCommandPayload,
+        sk_hex: &str,
+        network: NetworkId,
+    ) -> Result<Self, SecretKeyError> {
+        let keypair = Keypair::from_secret_hex(sk_hex)?;
+        Ok(Self::from_payload(payload, keypair, network))
+    }
+
+    /// Same as [SignedCommand::from_payload] with the keypair parsed from the base58check
+    /// form of its secret key exported by wallets
+    pub fn from_payload_with_base58_sk(
+        payload: SignedCommandPayload,
+        sk_base58: &str,
+        network: NetworkId,
+    ) -> Result<Self, SecretKeyError> {
+        let keypair = Keypair::from_secret_base58(sk_base58)?;
+        Ok(Self::from_payload(payload, keypair, network))
+    }
+
+    fn sign_with_context(
+        payload: SignedCommandPayload,
+        keypair: &Keypair,
+        ctx: &mut dyn Signer<SignedCommandPayload>,
+    ) -> Self {
+        debug_assert_no_zero_keys(&payload);
+        let signature = ctx.sign(keypair, &payload);
 
         SignedCommand {
             payload,
@@ -47,23 +283,454 @@ impl SignedCommand {
             signature,
         }
     }
+
+    /// The memo attached to the command
+    pub fn memo(&self) -> &SignedCommandMemo {
+        &self.payload.common.memo
+    }
+
+    /// The text of the memo attached to the command, if it holds text
+    pub fn memo_text(&self) -> Option<String> {
+        self.memo().as_text()
+    }
+
+    /// Fee paid to include the command in a block
+    pub fn fee(&self) -> Amount {
+        self.payload.common.fee
+    }
+
+    /// The graphql json of the command, see [ToGraphQLJson], indented for human inspection
+    /// such as the `--json` output of a command line tool
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(&self.to_graphql_json()).expect("json values always serialize")
+    }
+
+    /// Base58check address of the signer, e.g. for logging and indexing
+    pub fn signer_address(&self) -> String {
+        self.signer.into_address()
+    }
+
+    /// Stable identifier of the transaction, for a wallet to recognize a command it already
+    /// submitted: the base58check encoding of the blake2b digest of the random oracle input
+    /// of the payload. It only depends on the payload, so re-signing the same payload, with
+    /// another scheme or for another network, gives the same id.
+    ///
+    /// This is not the transaction hash of the daemon, which covers the signature too and
+    /// so differs between signatures of the same payload. Use it to deduplicate submissions,
+    /// not to look the command up on a node or in an explorer
+    pub fn transaction_id(&self) -> String {
+        let mut hasher = Blake2bVar::new(32).expect("Invalid Blake2bVar output size");
+        hasher.update(TRANSACTION_ID_DOMAIN);
+        hasher.update(&self.payload.to_roinput_bytes());
+        bs58::encode(hasher.finalize_boxed())
+            .with_check()
+            .into_string()
+    }
+
+    /// One line human readable summary for logs, as the derived `Debug` prints raw field
+    /// elements: the kind, source and receiver addresses, amounts in MINA, nonce and the
+    /// first bytes of the signature, e.g.
+    /// `payment B62q... -> B62q... amount 1729.000000000 fee 2.000000000 nonce 16 signature 11a36a8dfe5b857b...`
+    pub fn summary(&self) -> String {
+        let kind = match &self.payload.body {
+            SignedCommandPayloadBody::PaymentPayload(payment) => format!(
+                "payment {} -> {} amount {}",
+                payment.source_pk().into_address(),
+                payment.receiver_pk().into_address(),
+                payment.amount()
+            ),
+            SignedCommandPayloadBody::StakeDelegation(StakeDelegation::SetDelegate {
+                delegator,
+                new_delegate,
+            }) => format!(
+                "stake delegation {} -> {}",
+                delegator.into_address(),
+                new_delegate.into_address()
+            ),
+        };
+        let signature = self.signature.to_string();
+        format!(
+            "{} fee {} nonce {} signature {}...",
+            kind,
+            self.fee(),
+            self.nonce().0,
+            &signature[..SUMMARY_SIGNATURE_HEX_LEN.min(signature.len())]
+        )
+    }
+
+    /// Token the fee is paid in
+    pub fn fee_token(&self) -> TokenId {
+        self.payload.common.fee_token.clone()
+    }
+
+    /// Nonce of the fee payer account this command is applied at
+    pub fn nonce(&self) -> AccountNonce {
+        self.payload.common.nonce
+    }
+
+    /// If the command can be applied to a fee payer account at the given nonce.
+    /// A lower nonce was already used and a higher one leaves a gap, so only an equal one is valid
+    pub fn is_nonce_valid(&self, account_nonce: AccountNonce) -> bool {
+        self.nonce() == account_nonce
+    }
+
+    /// Global slot after which the command can no longer be included in a block
+    pub fn valid_until(&self) -> GlobalSlotNumber {
+        self.payload.common.valid_until
+    }
+
+    /// Length in bytes of the bin_prot encoding of the command, as used for fee per byte
+    /// estimates. The bytes are counted as they are written rather than buffered
+    pub fn serialized_size(&self) -> usize {
+        bin_prot::serialized_size(&SignedCommandV1::from(self.clone()))
+            .expect("signed commands always serialize to bin_prot")
+    }
+
+    /// If both commands have the same payload and signer, whatever their signatures.
+    /// Useful to deduplicate a transaction that was signed more than once, e.g. by signers
+    /// using different nonce derivations
+    pub fn same_payload(&self, other: &SignedCommand) -> bool {
+        self.payload == other.payload && self.signer == other.signer
+    }
+
+    /// Key identifying the slot this command takes in a mempool: its fee payer and nonce.
+    /// At most one command per key can be applied, the others are replacements
+    pub fn mempool_key(&self) -> (CompressedPubKey, AccountNonce) {
+        (
+            self.payload.common.fee_payer_pk.clone(),
+            self.payload.common.nonce,
+        )
+    }
+
+    /// Compare by fee rate, lowest first, so that a max heap pops the most profitable
+    /// command first. All signed commands have unit weight, so the fee rate is the fee
+    pub fn cmp_fee_rate(&self, other: &Self) -> std::cmp::Ordering {
+        self.payload.common.fee.0.cmp(&other.payload.common.fee.0)
+    }
+
+    /// Check that the command is signed for the expected network with the given scheme.
+    /// Addresses do not encode the network, only the domain string the signature commits to
+    /// does, so a signature that only verifies under the other network is reported as a mismatch
+    pub fn check_network(
+        &self,
+        expected: NetworkId,
+        scheme: SignatureScheme,
+    ) -> Result<(), NetworkCheckError> {
+        if context::with_signer_context(&expected, scheme, |ctx| self.verify(ctx)) {
+            return Ok(());
+        }
+        let other = match expected {
+            NetworkId::MAINNET => NetworkId::TESTNET,
+            NetworkId::TESTNET => NetworkId::MAINNET,
+        };
+        if context::with_signer_context(&other, scheme, |ctx| self.verify(ctx)) {
+            return Err(NetworkCheckError::NetworkMismatch {
+                expected,
+                found: other,
+            });
+        }
+        Err(NetworkCheckError::InvalidSignature)
+    }
+
+    /// Verify the signature for the network with the legacy scheme, then with the kimchi
+    /// scheme, for commands of an unknown era. Use [NetworkSignedCommand::verify] instead
+    /// when the scheme is known.
+    ///
+    /// Accepting either scheme means a signature only needs to be valid under one of them,
+    /// so a command signed before the fork also verifies after it and could be replayed on
+    /// a network that only accepts the other scheme. Callers need to check the era of the
+    /// command separately wherever that matters
+    pub fn verify_any_scheme(&self, network: NetworkId) -> bool {
+        [SignatureScheme::Legacy, SignatureScheme::Kimchi]
+            .into_iter()
+            .any(|scheme| context::with_signer_context(&network, scheme, |ctx| self.verify(ctx)))
+    }
+
+    /// Run the checks a wallet does before broadcasting: the signature verifies with ctx,
+    /// the nonce matches the fee payer account, the command has not expired and its tokens
+    /// pass [SignedCommandPayload::check_tokens].
+    /// All checks are run, and every one that fails is reported
+    pub fn validate<CTX>(
+        &self,
+        ctx: &mut CTX,
+        account_nonce: AccountNonce,
+        current_slot: GlobalSlotNumber,
+    ) -> Result<(), Vec<ValidationIssue>>
+    where
+        CTX: Signer<SignedCommandPayload> + ?Sized,
+    {
+        let mut issues = Vec::new();
+        if !self.verify(ctx) {
+            issues.push(ValidationIssue::InvalidSignature);
+        }
+        if !self.is_nonce_valid(account_nonce) {
+            issues.push(ValidationIssue::NonceMismatch {
+                expected: account_nonce,
+                found: self.nonce(),
+            });
+        }
+        if self.payload.common.is_expired(current_slot) {
+            issues.push(ValidationIssue::Expired {
+                valid_until: self.valid_until(),
+                current_slot,
+            });
+        }
+        if let Err(e) = self.payload.check_tokens() {
+            issues.push(ValidationIssue::Tokens(e));
+        }
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+}
+
+/// A failed check reported by [SignedCommand::validate]
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// The signature does not verify with the given context
+    #[error("Command signature is not valid")]
+    InvalidSignature,
+    /// The command nonce is not the one of the fee payer account
+    #[error("Command nonce is {}, expected the account nonce {}", found.0, expected.0)]
+    NonceMismatch {
+        /// Nonce of the fee payer account
+        expected: AccountNonce,
+        /// Nonce of the command
+        found: AccountNonce,
+    },
+    /// The command can no longer be included in a block
+    #[error("Command is valid until slot {}, current slot is {}", valid_until.0, current_slot.0)]
+    Expired {
+        /// Last slot the command is valid at
+        valid_until: GlobalSlotNumber,
+        /// Slot the command was checked at
+        current_slot: GlobalSlotNumber,
+    },
+    /// The command uses tokens the daemon rejects
+    #[error(transparent)]
+    Tokens(#[from] TokenError),
+    /// The fee payer account is not known, reported by
+    /// [StagedLedgerDiff::invalid_commands](crate::staged_ledger_diff::StagedLedgerDiff::invalid_commands)
+    #[error("Fee payer {0} is not a known account")]
+    UnknownFeePayer(String),
+}
+
+/// Error returned by [SignedCommandPayload::check_tokens]
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    /// Fees can only be paid in the default token
+    #[error("Fees must be paid in the default token, not {0:?}")]
+    NonDefaultFeeToken(TokenId),
+    /// The payment is of token id 0, which is reserved as invalid
+    #[error("Payment token id 0 is invalid")]
+    InvalidPaymentToken,
+}
+
+/// Group commands by fee payer, each group sorted by nonce so that gaps are easy to spot.
+/// Commands with the same nonce keep their input order.
+/// Groups are keyed by the fee payer wrapped in [OrdPubKey] as [CompressedPubKey] is not `Ord`
+pub fn group_by_fee_payer(
+    cmds: impl IntoIterator<Item = SignedCommand>,
+) -> BTreeMap<OrdPubKey, Vec<SignedCommand>> {
+    let mut groups: BTreeMap<OrdPubKey, Vec<SignedCommand>> = BTreeMap::new();
+    for cmd in cmds {
+        groups
+            .entry(OrdPubKey(cmd.payload.common.fee_payer_pk.clone()))
+            .or_default()
+            .push(cmd);
+    }
+    for group in groups.values_mut() {
+        group.sort_by_key(SignedCommand::nonce);
+    }
+    groups
+}
+
+/// Nonces missing from a batch of commands of one sender, from the account's current nonce
+/// up to the highest nonce in the batch. The daemon only applies commands in nonce order,
+/// so every command after the first missing nonce is stuck until it is filled. Nonces
+/// below the start nonce are already used and are ignored, as are duplicates
+pub fn detect_nonce_gaps<'a>(
+    cmds: impl IntoIterator<Item = &'a SignedCommand>,
+    start_nonce: AccountNonce,
+) -> Vec<AccountNonce> {
+    let nonces: BTreeSet<AccountNonce> = cmds
+        .into_iter()
+        .map(SignedCommand::nonce)
+        .filter(|nonce| *nonce >= start_nonce)
+        .collect();
+    let last = match nonces.iter().next_back() {
+        Some(last) => last.0,
+        None => return Vec::new(),
+    };
+    (start_nonce.0..last)
+        .map(AccountNonce)
+        .filter(|nonce| !nonces.contains(nonce))
+        .collect()
+}
+
+/// Verify a signature over the payload for the network with [SignatureScheme::DEFAULT],
+/// for clients that only verify and do not manage signer contexts. The context is cached
+/// per thread the same way as for [SignedCommand::from_payload]
+pub fn verify_signature(
+    payload: &SignedCommandPayload,
+    signer: &CompressedPubKey,
+    sig: &Signature,
+    network: NetworkId,
+) -> bool {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!(
+        "verify_command",
+        network = ?network,
+        signer = %signer.into_address(),
+        kind = payload.body.kind_name(),
+    )
+    .entered();
+    let signer = match signer.to_pub_key() {
+        Ok(signer) => signer,
+        Err(_) => return false,
+    };
+    context::with_signer_context(&network, SignatureScheme::DEFAULT, |ctx| {
+        ctx.verify(sig, &signer, payload)
+    })
+}
+
+/// Error returned by [SignedCommand::check_network]
+#[derive(Debug, Error, Clone)]
+pub enum NetworkCheckError {
+    /// The signature only verifies under the other network
+    #[error("Command is signed for {found:?}, expected {expected:?}")]
+    NetworkMismatch {
+        /// Network the command was expected to be signed for
+        expected: NetworkId,
+        /// Network the signature verifies under
+        found: NetworkId,
+    },
+    /// The signature does not verify under either network
+    #[error("Command signature is not valid for any network")]
+    InvalidSignature,
+}
+
+/// Signature scheme a command is signed with
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum SignatureScheme {
+    /// Poseidon with the legacy parameters, used by the current mainnet
+    Legacy,
+    /// Poseidon with the kimchi parameters
+    Kimchi,
+}
+
+impl SignatureScheme {
+    /// Scheme used by [SignedCommand::from_payload], selected at build time.
+    ///
+    /// This is [SignatureScheme::Legacy] unless the `kimchi-signatures` feature is enabled.
+    /// Deployments targeting post-fork networks enable that feature, which switches it to
+    /// [SignatureScheme::Kimchi] without any change to calling code. Cargo unifies features
+    /// across a build, so any crate enabling it switches the default for all of them.
+    /// Code that must sign for a specific scheme regardless of the build should use
+    /// [NetworkSignedCommand::from_payload] instead.
+    #[cfg(not(feature = "kimchi-signatures"))]
+    pub const DEFAULT: Self = Self::Legacy;
+
+    /// Scheme used by [SignedCommand::from_payload], selected at build time.
+    ///
+    /// This is [SignatureScheme::Kimchi] as the `kimchi-signatures` feature is enabled.
+    #[cfg(feature = "kimchi-signatures")]
+    pub const DEFAULT: Self = Self::Kimchi;
+}
+
+impl Default for SignatureScheme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// A [SignedCommand] together with the network and signature scheme it was signed for,
+/// so that it can be verified without the caller picking a signer context.
+/// This context is not part of the wire format, only the inner command is serialized
+#[derive(Clone, Debug)]
+pub struct NetworkSignedCommand {
+    /// The signed command
+    pub command: SignedCommand,
+    /// Network the command was signed for
+    pub network: NetworkId,
+    /// Scheme the command was signed with
+    pub scheme: SignatureScheme,
+}
+
+impl NetworkSignedCommand {
+    /// Sign a SignedCommandPayload for the given network and scheme
+    pub fn from_payload(
+        payload: SignedCommandPayload,
+        keypair: Keypair,
+        network: NetworkId,
+        scheme: SignatureScheme,
+    ) -> Self {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!(
+            "sign_command",
+            network = ?network,
+            scheme = ?scheme,
+            signer = %keypair.public.into_address(),
+            kind = payload.body.kind_name(),
+        )
+        .entered();
+        let command = context::with_signer_context(&network, scheme, |ctx| {
+            SignedCommand::sign_with_context(payload, &keypair, ctx)
+        });
+        Self {
+            command,
+            network,
+            scheme,
+        }
+    }
+
+    /// Verify the command with a signer context for the network and scheme it carries
+    pub fn verify(&self) -> bool {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!(
+            "verify_command",
+            network = ?self.network,
+            scheme = ?self.scheme,
+            signer = %self.command.signer_address(),
+            kind = self.command.payload.body.kind_name(),
+        )
+        .entered();
+        context::with_signer_context(&self.network, self.scheme, |ctx| self.command.verify(ctx))
+    }
 }
 
-impl<CTX> Verifiable<CTX> for SignedCommand
-where
-    CTX: Signer<SignedCommandPayload>,
-{
-    fn verify(&self, ctx: &mut CTX) -> bool {
-        // do a slightly sketchy conversion via address string. Safe to unwrap as we know it was valid to begin with
-        // TODO replace this with a proper `.into` conversion when supported in proof-systems
-        let signer_uncompressed = PubKey::from_address(&self.signer.into_address()).unwrap();
-        ctx.verify(&self.signature, &signer_uncompressed, &self.payload)
+impl From<NetworkSignedCommand> for SignedCommand {
+    fn from(cmd: NetworkSignedCommand) -> Self {
+        cmd.command
+    }
+}
+
+impl Signable for SignedCommand {
+    type Payload = SignedCommandPayload;
+
+    fn payload(&self) -> &SignedCommandPayload {
+        &self.payload
+    }
+
+    fn signer(&self) -> &CompressedPubKey {
+        &self.signer
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
     }
 }
 
 /// The part of a signed command that needs to be serialized and signed
-#[derive(Clone, Eq, PartialEq, Debug, AutoFrom)]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, AutoFrom)]
 #[auto_from(mina_serialization_types::staged_ledger_diff::SignedCommandPayload)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SignedCommandPayloadJson", from = "SignedCommandPayloadJson")
+)]
 pub struct SignedCommandPayload {
     /// Fields common to all command types
     pub common: SignedCommandPayloadCommon,
@@ -71,11 +738,184 @@ pub struct SignedCommandPayload {
     pub body: SignedCommandPayloadBody,
 }
 
+impl_from_with_proxy!(
+    SignedCommandPayload,
+    mina_serialization_types::staged_ledger_diff::SignedCommandPayload,
+    SignedCommandPayloadJson
+);
+
+impl JsonSerializationType<'_> for SignedCommandPayload {
+    type T = SignedCommandPayloadJson;
+}
+
+/// Assemble a payment payload from its parts
+///
+/// ```
+/// use mina_rs_base::types::*;
+/// use proof_systems::mina_signer::CompressedPubKey;
+///
+/// let source = CompressedPubKey::from_address(
+///     "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+/// )
+/// .unwrap();
+/// let receiver = CompressedPubKey::from_address(
+///     "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+/// )
+/// .unwrap();
+/// let payment = PaymentPayload::with_default_token(source.clone(), receiver, 1000);
+/// let payload = SignedCommandPayload::from((
+///     SignedCommandPayloadCommon::minimal(source),
+///     payment.clone(),
+/// ));
+/// assert_eq!(payload.body, SignedCommandPayloadBody::PaymentPayload(payment));
+/// ```
+impl From<(SignedCommandPayloadCommon, PaymentPayload)> for SignedCommandPayload {
+    fn from((common, payment): (SignedCommandPayloadCommon, PaymentPayload)) -> Self {
+        Self {
+            common,
+            body: SignedCommandPayloadBody::PaymentPayload(payment),
+        }
+    }
+}
+
+/// Assemble a stake delegation payload from its parts
+///
+/// ```
+/// use mina_rs_base::types::*;
+/// use mina_rs_base::user_commands::signed_command::StakeDelegation;
+/// use proof_systems::mina_signer::CompressedPubKey;
+///
+/// let delegator = CompressedPubKey::from_address(
+///     "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+/// )
+/// .unwrap();
+/// let new_delegate = CompressedPubKey::from_address(
+///     "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+/// )
+/// .unwrap();
+/// let delegation = StakeDelegation::SetDelegate {
+///     delegator: delegator.clone(),
+///     new_delegate,
+/// };
+/// let payload = SignedCommandPayload::from((
+///     SignedCommandPayloadCommon::minimal(delegator),
+///     delegation.clone(),
+/// ));
+/// assert_eq!(payload.body, SignedCommandPayloadBody::StakeDelegation(delegation));
+/// ```
+impl From<(SignedCommandPayloadCommon, StakeDelegation)> for SignedCommandPayload {
+    fn from((common, delegation): (SignedCommandPayloadCommon, StakeDelegation)) -> Self {
+        Self {
+            common,
+            body: SignedCommandPayloadBody::StakeDelegation(delegation),
+        }
+    }
+}
+
 impl SignedCommandPayload {
-    /// Convert into a signed command by signing with the given keypair and network ID
+    /// Convert into a signed command by signing with the given keypair and network ID,
+    /// using [SignatureScheme::DEFAULT]
     pub fn into_signed_command(self, keypair: Keypair, network: NetworkId) -> SignedCommand {
         SignedCommand::from_payload(self, keypair, network)
     }
+
+    /// Sign for both networks with [SignatureScheme::DEFAULT], returning the testnet and
+    /// the mainnet command in that order. The signer public key is compressed once and
+    /// shared by both commands
+    pub fn sign_both(&self, keypair: &Keypair) -> (SignedCommand, SignedCommand) {
+        let testnet = SignedCommand::sign_ref(self, keypair, NetworkId::TESTNET);
+        let signature =
+            context::with_signer_context(&NetworkId::MAINNET, SignatureScheme::DEFAULT, |ctx| {
+                ctx.sign(keypair, self)
+            });
+        let mainnet = SignedCommand {
+            signature,
+            ..testnet.clone()
+        };
+        (testnet, mainnet)
+    }
+
+    /// Check the token ids against the rules of the daemon's transaction logic, which drops
+    /// commands breaking them. The fee token must always be the default token, as fees can
+    /// only be paid in MINA. A payment may transfer another token than the fee token, but
+    /// not the invalid token id 0. Stake delegations have no token of their own.
+    pub fn check_tokens(&self) -> Result<(), TokenError> {
+        if self.common.fee_token != TokenId::default_token() {
+            return Err(TokenError::NonDefaultFeeToken(
+                self.common.fee_token.clone(),
+            ));
+        }
+        match &self.body {
+            SignedCommandPayloadBody::PaymentPayload(payment)
+                if *payment.token_id() == TokenId::default() =>
+            {
+                Err(TokenError::InvalidPaymentToken)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The random oracle input packed into field elements, in the order they are absorbed
+    /// by the hasher when signing. Note that the input does not depend on the network,
+    /// only the domain string used to initialize the hasher does.
+    ///
+    /// Intended for debugging signature mismatches against the OCaml reference implementation
+    pub fn to_roinput_fields(&self) -> Vec<Fp> {
+        self.to_roinput().to_fields()
+    }
+
+    /// The random oracle input as packed bytes (all field elements followed by all bits)
+    ///
+    /// Intended for debugging signature mismatches against the OCaml reference implementation
+    pub fn to_roinput_bytes(&self) -> Vec<u8> {
+        self.to_roinput().to_bytes()
+    }
+
+    /// Number of bits in the random oracle input, counting each field element at its full
+    /// bit size followed by the memo, the tag and all other packed bits
+    pub fn roinput_bit_len(&self) -> usize {
+        ROINPUT_FIELDS * Fp::size_in_bits()
+            + COMMON_BITS
+            + self.common.memo.0.len() * 8
+            + TAG_BITS
+            + BODY_BITS
+    }
+}
+
+/// Debug check that integers appended to the random oracle input fit in a field element
+/// below the modulus. This always holds for u64 and the Pallas base field, it guards
+/// against a change of field or of the appended types wrapping values silently
+fn debug_assert_fits_field(values: &[u64]) {
+    for &value in values {
+        debug_assert!(
+            ((u64::BITS - value.leading_zeros()) as usize) < Fp::size_in_bits(),
+            "{value} does not fit in a field element"
+        );
+    }
+}
+
+/// Debug check that none of the public keys appended to the random oracle input has x = 0.
+/// No Mina key is the identity, so such a key can only come from a corrupt payload, whose
+/// degenerate preimage must not be signed
+fn debug_assert_no_zero_keys(payload: &SignedCommandPayload) {
+    let keys = match &payload.body {
+        SignedCommandPayloadBody::PaymentPayload(pp) => [
+            ("fee payer", &payload.common.fee_payer_pk),
+            ("source", &pp.source_pk),
+            ("receiver", &pp.receiver_pk),
+        ],
+        SignedCommandPayloadBody::StakeDelegation(StakeDelegation::SetDelegate {
+            delegator,
+            new_delegate,
+        }) => [
+            ("fee payer", &payload.common.fee_payer_pk),
+            ("delegator", delegator),
+            ("delegate", new_delegate),
+        ],
+    };
+    for (role, key) in keys {
+        debug_assert!(!key.x.is_zero(), "The {role} public key is zero");
+    }
 }
 
 impl Hashable for SignedCommandPayload {
@@ -83,8 +923,10 @@ impl Hashable for SignedCommandPayload {
 
     fn to_roinput(&self) -> ROInput {
         let mut roi = ROInput::new();
+        debug_assert_fits_field(&[self.common.fee.0, self.common.fee_token.0]);
         match &self.body {
             SignedCommandPayloadBody::PaymentPayload(pp) => {
+                debug_assert_fits_field(&[pp.token_id.0, pp.amount.0]);
                 roi = roi
                     .append_field(self.common.fee_payer_pk.x)
                     .append_field(pp.source_pk.x)
@@ -128,8 +970,8 @@ impl Hashable for SignedCommandPayload {
 
                     roi.append_bool(delegator.is_odd)
                         .append_bool(new_delegate.is_odd)
-                        .append_u64(1)
-                        .append_u64(0)
+                        .append_u64(DELEGATION_TOKEN_ID.0)
+                        .append_u64(DELEGATION_AMOUNT.0)
                         .append_bool(false) // this is the token locked field. Not sure where this belongs yet
                 }
             },
@@ -164,8 +1006,63 @@ pub struct SignedCommandPayloadCommon {
     pub memo: SignedCommandMemo,
 }
 
+impl Hash for SignedCommandPayloadCommon {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.fee.hash(state);
+        self.fee_token.hash(state);
+        self.fee_payer_pk.hash_key(state);
+        self.nonce.hash(state);
+        self.valid_until.hash(state);
+        self.memo.hash(state);
+    }
+}
+
+impl SignedCommandPayloadCommon {
+    /// Common fields with no fee, the default token, nonce 0, no expiry and an empty memo,
+    /// the same defaults the command builders start from. Handy for payloads where only
+    /// the body matters
+    ///
+    /// ```
+    /// use mina_rs_base::types::*;
+    /// use proof_systems::mina_signer::CompressedPubKey;
+    ///
+    /// let source = CompressedPubKey::from_address(
+    ///     "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+    /// )
+    /// .unwrap();
+    /// let receiver = CompressedPubKey::from_address(
+    ///     "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+    /// )
+    /// .unwrap();
+    /// let payload = SignedCommandPayload {
+    ///     common: SignedCommandPayloadCommon::minimal(source.clone()),
+    ///     body: SignedCommandPayloadBody::PaymentPayload(PaymentPayload::with_default_token(
+    ///         source, receiver, 1000,
+    ///     )),
+    /// };
+    /// assert_eq!(payload.common.fee, Amount(0));
+    /// assert!(payload.common.valid_until.is_forever());
+    /// ```
+    pub fn minimal(fee_payer_pk: CompressedPubKey) -> Self {
+        Self {
+            fee: Amount(0),
+            fee_token: TokenId::default_token(),
+            fee_payer_pk,
+            nonce: AccountNonce(0),
+            valid_until: GlobalSlotNumber::forever(),
+            memo: SignedCommandMemo::default(),
+        }
+    }
+
+    /// If the command can no longer be included in a block at the given global slot.
+    /// A `valid_until` of [GlobalSlotNumber::forever] never expires
+    pub fn is_expired(&self, current_slot: GlobalSlotNumber) -> bool {
+        !self.valid_until.is_forever() && self.valid_until.0 < current_slot.0
+    }
+}
+
 /// Enum of variable fields in a signed command
-#[derive(Clone, Eq, PartialEq, Debug, AutoFrom)]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, AutoFrom)]
 #[auto_from(mina_serialization_types::staged_ledger_diff::SignedCommandPayloadBody)]
 pub enum SignedCommandPayloadBody {
     /// Payment transfer fields
@@ -175,6 +1072,17 @@ pub enum SignedCommandPayloadBody {
     // FIXME: other variants are not covered by current test block
 }
 
+impl SignedCommandPayloadBody {
+    /// Name of the kind of body, recorded in tracing spans
+    #[cfg(feature = "tracing")]
+    fn kind_name(&self) -> &'static str {
+        match self {
+            Self::PaymentPayload(_) => "payment",
+            Self::StakeDelegation(_) => "stake_delegation",
+        }
+    }
+}
+
 /// Enum of variable fields for stake delegation
 #[derive(Clone, Eq, PartialEq, Debug, AutoFrom)]
 #[auto_from(mina_serialization_types::staged_ledger_diff::StakeDelegation)]
@@ -188,17 +1096,44 @@ pub enum StakeDelegation {
     },
 }
 
+impl Hash for StakeDelegation {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Self::SetDelegate {
+                delegator,
+                new_delegate,
+            } => {
+                delegator.hash_key(state);
+                new_delegate.hash_key(state);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::user_commands::SignedCommand;
+    use mina_serialization_types::{json::SignatureJson, v1::SignatureV1};
     use proof_systems::mina_signer;
     use proof_systems::mina_signer::{CompressedPubKey, Keypair, NetworkId, PubKey, Signer};
+    use proof_systems::o1_utils::FieldHelpers;
+
+    // The reference vectors are legacy signatures whichever scheme from_payload defaults to
+    fn sign_legacy(
+        payload: SignedCommandPayload,
+        keypair: Keypair,
+        network: NetworkId,
+    ) -> SignedCommand {
+        NetworkSignedCommand::from_payload(payload, keypair, network, SignatureScheme::Legacy)
+            .into()
+    }
 
     // Credit to the proof-systems repo tests from where this macro was taken
     macro_rules! assert_sign_verify_tx {
-        ($tx_type:expr, $sec_key:expr, $source_address:expr, $receiver_address:expr, $amount:expr, $fee:expr,
-         $nonce:expr, $valid_until:expr, $memo:expr, $testnet_target:expr, $mainnet_target:expr) => {
+        (TransactionType::PaymentTx, $sec_key:expr, $source_address:expr, $receiver_address:expr,
+         $amount:expr, $fee:expr, $nonce:expr, $valid_until:expr, $memo:expr,
+         $testnet_target:expr, $mainnet_target:expr) => {
             let kp = Keypair::from_hex($sec_key).expect("failed to create keypair");
             assert_eq!(
                 kp.public,
@@ -216,56 +1151,69 @@ mod tests {
             .valid_until($valid_until)
             .memo(SignedCommandMemo::try_from_text($memo).expect("invalid memo string"));
 
-            let mut payload = builder.build();
-
-            let testnet_cmd =
-                SignedCommand::from_payload(payload.clone(), kp.clone(), NetworkId::TESTNET);
-            let testnet_sig = &testnet_cmd.signature;
-            let mainnet_cmd =
-                SignedCommand::from_payload(payload.clone(), kp.clone(), NetworkId::MAINNET);
-            let mainnet_sig = &mainnet_cmd.signature;
-
-            // Context for verification
-            let mut testnet_ctx = mina_signer::create_legacy(NetworkId::TESTNET);
-            let mut mainnet_ctx = mina_signer::create_legacy(NetworkId::MAINNET);
-
-            // Signing checks
-            assert_ne!(testnet_sig, mainnet_sig); // Testnet and mainnet sigs are not equal
-            assert_eq!(testnet_sig.to_string(), $testnet_target); // Testnet target check
-            assert_eq!(mainnet_sig.to_string(), $mainnet_target); // Mainnet target check
-
-            // Verification checks
-            assert_eq!(testnet_ctx.verify(&testnet_sig, &kp.public, &payload), true);
-            assert_eq!(mainnet_ctx.verify(&mainnet_sig, &kp.public, &payload), true);
-
-            // Fails verification on the other network
-            assert_eq!(
-                mainnet_ctx.verify(&testnet_sig, &kp.public, &payload),
-                false
+            test_util::assert_command_matches_vectors(
+                builder.build(),
+                &kp,
+                $testnet_target,
+                $mainnet_target,
             );
+        };
+        // The source is the delegator and the receiver the new delegate. Delegations carry no
+        // amount, the vectors pass 0
+        (TransactionType::DelegationTx, $sec_key:expr, $source_address:expr,
+         $receiver_address:expr, 0, $fee:expr, $nonce:expr, $valid_until:expr, $memo:expr,
+         $testnet_target:expr, $mainnet_target:expr) => {
+            let kp = Keypair::from_hex($sec_key).expect("failed to create keypair");
             assert_eq!(
-                testnet_ctx.verify(&mainnet_sig, &kp.public, &payload),
-                false
+                kp.public,
+                PubKey::from_address($source_address).expect("invalid delegator address")
             );
 
-            // Flip some bits, its should no longer pass verification
-            payload.common.valid_until.0 = !payload.common.valid_until.0;
-            assert_eq!(
-                mainnet_ctx.verify(&testnet_sig, &kp.public, &payload),
-                false
-            );
-            assert_eq!(
-                testnet_ctx.verify(&mainnet_sig, &kp.public, &payload),
-                false
+            let builder = builder::SignedDelegationCommandBuilder::new(
+                CompressedPubKey::from_address($source_address).expect("invalid delegator address"),
+                CompressedPubKey::from_address($receiver_address)
+                    .expect("invalid new delegate address"),
+                $fee,
+                $nonce,
+            )
+            .valid_until($valid_until)
+            .memo(SignedCommandMemo::try_from_text($memo).expect("invalid memo string"));
+
+            test_util::assert_command_matches_vectors(
+                builder.build(),
+                &kp,
+                $testnet_target,
+                $mainnet_target,
             );
+        };
+    }
 
-            // Also check using the implementation of verify
-            assert_eq!(testnet_cmd.verify(&mut testnet_ctx), true);
-            assert_eq!(mainnet_cmd.verify(&mut mainnet_ctx), true);
+    // Checks a delegation signed by the reference implementation and included in a mainnet block
+    macro_rules! assert_verify_delegation_reference {
+        ($delegator_address:expr, $new_delegate_address:expr, $fee:expr, $nonce:expr,
+         $valid_until:expr, $memo:expr, $mainnet_signature:expr) => {
+            let payload = builder::SignedDelegationCommandBuilder::new(
+                CompressedPubKey::from_address($delegator_address)
+                    .expect("invalid delegator address"),
+                CompressedPubKey::from_address($new_delegate_address)
+                    .expect("invalid new delegate address"),
+                $fee,
+                $nonce,
+            )
+            .valid_until($valid_until)
+            .memo(SignedCommandMemo::try_from_text($memo).expect("invalid memo string"))
+            .build();
+            let signature: Signature = SignatureV1::from(
+                serde_json::from_value::<SignatureJson>(serde_json::json!($mainnet_signature))
+                    .expect("invalid signature"),
+            )
+            .into();
+            let signer = PubKey::from_address($delegator_address).expect("invalid signer address");
 
-            // Ensure they fail on the other network
-            assert_eq!(testnet_cmd.verify(&mut mainnet_ctx), false);
-            assert_eq!(mainnet_cmd.verify(&mut testnet_ctx), false);
+            let mut testnet_ctx = mina_signer::create_legacy(NetworkId::TESTNET);
+            let mut mainnet_ctx = mina_signer::create_legacy(NetworkId::MAINNET);
+            assert!(mainnet_ctx.verify(&signature, &signer, &payload));
+            assert!(!testnet_ctx.verify(&signature, &signer, &payload));
         };
     }
 
@@ -336,4 +1284,1292 @@ mod tests {
             /* mainnet signature */ "058ed7fb4e17d9d400acca06fe20ca8efca2af4ac9a3ed279911b0bf93c45eea0e8961519b703c2fd0e431061d8997cac4a7574e622c0675227d27ce2ff357d9"
         );
     }
+
+    #[test]
+    fn sign_delegation_test_1() {
+        assert_sign_verify_tx!(
+            /* Transaction type   */ TransactionType::DelegationTx,
+            /* sender secret key  */ "164244176fddb5d769b7de2027469d027ad428fadcc0c02396e6280142efb718",
+            /* source address     */ "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+            /* receiver address   */ "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+            /* amount             */ 0,
+            /* fee                */ 2000000000,
+            /* nonce              */ 16,
+            /* valid until        */ 1337,
+            /* memo               */ "Delewho?",
+            /* testnet signature  */ "30797d7d0426e54ff195d1f94dc412300f900cc9e84990603939a77b3a4d2fc11ebab12857b47c481c182abe147279732549f0fd49e68d5541f825e9d1e6fa04",
+            /* mainnet signature  */ "0904e9521a95334e3f6757cb0007ec8af3322421954255e8d263d0616910b04d213344f8ec020a4b873747d1cbb07296510315a2ec76e52150a4c765520d387f"
+        );
+    }
+
+    #[test]
+    fn sign_delegation_test_2() {
+        assert_sign_verify_tx!(
+            /* Transaction type   */ TransactionType::DelegationTx,
+            /* sender secret key  */ "20f84123a26e58dd32b0ea3c80381f35cd01bc22a20346cc65b0a67ae48532ba",
+            /* source address     */ "B62qkfHpLpELqpMK6ZvUTJ5wRqKDRF3UHyJ4Kv3FU79Sgs4qpBnx5RR",
+            /* receiver address   */ "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+            /* amount             */ 0,
+            /* fee                */ 2000000000,
+            /* nonce              */ 0,
+            /* valid until        */ 4294967295,
+            /* memo               */ "",
+            /* testnet signature  */ "07e9f88fc671ed06781f9edb233fdbdee20fa32303015e795747ad9e43fcb47b3ce34e27e31f7c667756403df3eb4ce670d9175dd0ae8490b273485b71c56066",
+            /* mainnet signature  */ "2406ab43f8201bd32bdd81b361fdb7871979c0eec4e3b7a91edf87473963c8a4069f4811ebc5a0e85cbb4951bffe93b638e230ce5a250cb08d2c250113a1967c"
+        );
+    }
+
+    #[test]
+    fn sign_delegation_test_3() {
+        assert_sign_verify_tx!(
+            /* Transaction type   */ TransactionType::DelegationTx,
+            /* sender secret key  */ "3414fc16e86e6ac272fda03cf8dcb4d7d47af91b4b726494dab43bf773ce1779",
+            /* source address     */ "B62qoG5Yk4iVxpyczUrBNpwtx2xunhL48dydN53A2VjoRwF8NUTbVr4",
+            /* receiver address   */ "B62qrKG4Z8hnzZqp1AL8WsQhQYah3quN1qUj3SyfJA8Lw135qWWg1mi",
+            /* amount             */ 0,
+            /* fee                */ 42000000000,
+            /* nonce              */ 1,
+            /* valid until        */ 4294967295,
+            /* memo               */ "more delegates, more fun........",
+            /* testnet signature  */ "1ff9f77fed4711e0ebe2a7a46a7b1988d1b62a850774bf299ec71a24d5ebfdd81d04a570e4811efe867adefe3491ba8b210f24bd49ec2b9e3b96a2b2b4f7b76a",
+            /* mainnet signature  */ "36a80d0421b9c0cbfa08ea95b27f401df108b30213ae138f1f5978ffc59606cf2b64758db9d26bd9c5b908423338f7445c8f0a07520f2154bbb62926aa0cb8fa"
+        );
+    }
+
+    #[test]
+    fn sign_delegation_test_4() {
+        assert_sign_verify_tx!(
+            /* Transaction type   */ TransactionType::DelegationTx,
+            /* sender secret key  */ "336eb4a19b3d8905824b0f2254fb495573be302c17582748bf7e101965aa4774",
+            /* source address     */ "B62qrKG4Z8hnzZqp1AL8WsQhQYah3quN1qUj3SyfJA8Lw135qWWg1mi",
+            /* receiver address   */ "B62qkfHpLpELqpMK6ZvUTJ5wRqKDRF3UHyJ4Kv3FU79Sgs4qpBnx5RR",
+            /* amount             */ 0,
+            /* fee                */ 1202056900,
+            /* nonce              */ 0,
+            /* valid until        */ 577216,
+            /* memo               */ "",
+            /* testnet signature  */ "26ca6b95dee29d956b813afa642a6a62cd89b1929320ed6b099fd191a217b08d2c9a54ba1c95e5000b44b93cfbd3b625e20e95636f1929311473c10858a27f09",
+            /* mainnet signature  */ "093f9ef0e4e051279da0a3ded85553847590ab739ee1bfd59e5bb30f98ed8a001a7a60d8506e2572164b7a525617a09f17e1756ac37555b72e01b90f37271595"
+        );
+    }
+
+    #[test]
+    fn verify_delegation_reference_113267() {
+        assert_verify_delegation_reference!(
+            /* delegator address     */ "B62qp5MgMnCrd2bB8pGpPVmAntym3Qfx3vu7wBWwJ5p9e6eU9srYx9v",
+            /* new delegate address  */ "B62qns9cPvDwckhJXHpWZZ8b8T8oUgoF4Enpax5zNVBYYMtQwHf4Cmp",
+            /* fee                   */ 10100000,
+            /* nonce                 */ 0,
+            /* valid until           */ 4294967295,
+            /* memo                  */ "",
+            /* mainnet signature     */ "7mX2tu8ZxQbYkYWCCUKK6CpWhsAtPtiBWYkaa2BuJLJt4p934TfspRi7End8RKm5MBWXpqXGP4WxLv2NNjUPC1UiPM6KvnRn"
+        );
+    }
+
+    #[test]
+    fn verify_delegation_reference_147571() {
+        // Stake delegation taken from mainnet block 3NKwrze6FvGQCCF6L7Q2JLvwgnsm56hwSny9kUyjbSUr8oqu1MGp,
+        // the payload of mainnet_delegation_147571
+        assert_verify_delegation_reference!(
+            /* delegator address     */ "B62qkPv5qdMBHRcZtPXJEegRvEE9aLwNsyBx4ZZAQ94XKixJNdD26aY",
+            /* new delegate address  */ "B62qjSytpSK7aEauBprjXDSZwc9ai4YMv9tpmXLQK14Vy941YV36rMz",
+            /* fee                   */ 10100000,
+            /* nonce                 */ 4,
+            /* valid until           */ 4294967295,
+            /* memo                  */ "",
+            /* mainnet signature     */ "7mXSENtPjoNij4o3XruxLw4BbY7qogNkyGtHWhu8ffnYDpqHDE3rbiydfgYDiCpgq54AviMfxJfdPTyYRcJSobNan2FWnzD2"
+        );
+    }
+
+    // Mainnet payment signed by the reference implementation, given as (source, receiver,
+    // amount, fee, nonce, valid until, base58 signature). Every one of these has the empty memo
+    fn mainnet_payment_reference(
+        source_address: &str,
+        receiver_address: &str,
+        amount: u64,
+        fee: u64,
+        nonce: u32,
+        valid_until: u32,
+        signature: &str,
+    ) -> (SignedCommandPayload, Signature, PubKey) {
+        let payload = builder::SignedTransferCommandBuilder::new(
+            CompressedPubKey::from_address(source_address).expect("invalid source address"),
+            CompressedPubKey::from_address(receiver_address).expect("invalid receiver address"),
+            amount,
+            fee,
+            nonce,
+        )
+        .valid_until(valid_until)
+        .build();
+        let signature: Signature = SignatureV1::from(
+            serde_json::from_value::<SignatureJson>(serde_json::json!(signature))
+                .expect("invalid signature"),
+        )
+        .into();
+        let signer = PubKey::from_address(source_address).expect("invalid signer address");
+        (payload, signature, signer)
+    }
+
+    // Reads `len` bits of the ROInput of the payload at the given bit offset, least
+    // significant bit first
+    fn read_roinput_bits(payload: &SignedCommandPayload, offset: usize, len: usize) -> u64 {
+        let bytes = payload.to_roinput_bytes();
+        (0..len).fold(0u64, |acc, i| {
+            let bit = (bytes[(offset + i) / 8] >> ((offset + i) % 8)) & 1;
+            acc | (bit as u64) << i
+        })
+    }
+
+    // Bit offsets of the numeric fields in the ROInput of a payment, after the three 255 bit
+    // field elements
+    const FEE_OFFSET: usize = 3 * 255;
+    const NONCE_OFFSET: usize = FEE_OFFSET + 64 + 64 + 1;
+    const VALID_UNTIL_OFFSET: usize = NONCE_OFFSET + 32;
+
+    fn amount_offset(payload: &SignedCommandPayload) -> usize {
+        // The amount is followed only by the token locked bit
+        payload.roinput_bit_len() - 1 - 64
+    }
+
+    // The reference signature verifies the payload as is, and no longer does once `mutate`
+    // reorders the bytes of a single numeric field
+    fn assert_byte_order_is_signed(
+        payload: &SignedCommandPayload,
+        signature: &Signature,
+        signer: &PubKey,
+        mutate: impl FnOnce(&mut SignedCommandPayload),
+    ) {
+        let mut mainnet_ctx = mina_signer::create_legacy(NetworkId::MAINNET);
+        assert!(mainnet_ctx.verify(signature, signer, payload));
+
+        let mut swapped = payload.clone();
+        mutate(&mut swapped);
+        assert_ne!(
+            &swapped, payload,
+            "the swapped field must change the payload"
+        );
+        assert!(!mainnet_ctx.verify(signature, signer, &swapped));
+    }
+
+    #[test]
+    fn reference_vectors_nonce_byte_order() {
+        // Consecutive mainnet payments of block 147571 that differ only in their nonce
+        let (payload_416, signature_416, signer) = mainnet_payment_reference(
+            "B62qre3erTHfzQckNuibViWQGyyKwZseztqrjPZBv6SQF384Rg6ESAy",
+            "B62qjYanmV7y9njVeH5UHkz3GYBm7xKir1rAnoY4KsEYUGLMiU45FSM",
+            1000,
+            1000000,
+            207416,
+            4294967295,
+            "7mXNGQBLaw4EfZ59U7KbTe7t2QVvar83tDeABaehgji87QSdNQ8jjJ9QFCTRPQ1G89AXjUwB2TXzRNqiz9sZsZ3vcr62TnZ3",
+        );
+        let (payload_417, signature_417, _) = mainnet_payment_reference(
+            "B62qre3erTHfzQckNuibViWQGyyKwZseztqrjPZBv6SQF384Rg6ESAy",
+            "B62qjYanmV7y9njVeH5UHkz3GYBm7xKir1rAnoY4KsEYUGLMiU45FSM",
+            1000,
+            1000000,
+            207417,
+            4294967295,
+            "7mXT7iNcyzyCGSfz39Frqd12XSKLsMThySaLcrio1XewNW1GgPocfnoYFr5pSexYEc3Eu5ayxEZvp73oLnN9uFbXbv35AUTj",
+        );
+
+        let mut mainnet_ctx = mina_signer::create_legacy(NetworkId::MAINNET);
+        assert!(mainnet_ctx.verify(&signature_417, &signer, &payload_417));
+        assert!(!mainnet_ctx.verify(&signature_416, &signer, &payload_417));
+        assert!(!mainnet_ctx.verify(&signature_417, &signer, &payload_416));
+
+        for payload in [&payload_416, &payload_417] {
+            assert_eq!(
+                read_roinput_bits(payload, NONCE_OFFSET, 32),
+                payload.common.nonce.0 as u64
+            );
+        }
+        assert_byte_order_is_signed(&payload_416, &signature_416, &signer, |p| {
+            p.common.nonce.0 = p.common.nonce.0.swap_bytes()
+        });
+    }
+
+    #[test]
+    fn reference_vectors_fee_byte_order() {
+        // Mainnet payment of block 117896, with a fee of 0.2001 MINA
+        let (payload, signature, signer) = mainnet_payment_reference(
+            "B62qnqEqsuH7kST9ZrbksRzihXD2tgHfvq9TF73XKAMj47gisT9xsJ5",
+            "B62qnsHmPQpZSKnrp978ZHFYwCJFBZtY1qE3UD97dd7taQarEV6ZpuG",
+            16640000000000,
+            200100000,
+            1,
+            4294967295,
+            "7mX2BWdRnVDRrdhneyhrD5by7F2Xe1d9oEPerkC1qtr3fLXHRquaLE1FtT8cvLapqbcpEGNzUqxedgJ9bzeeYVLNGrdiBsAD",
+        );
+
+        assert_eq!(read_roinput_bits(&payload, FEE_OFFSET, 64), 200100000);
+        assert_byte_order_is_signed(&payload, &signature, &signer, |p| {
+            p.common.fee.0 = p.common.fee.0.swap_bytes()
+        });
+    }
+
+    #[test]
+    fn reference_vectors_amount_byte_order() {
+        // Mainnet payments of blocks 117896 and 147571, with amounts wider than 32 bits
+        for (source, receiver, amount, fee, nonce, signature) in [
+            (
+                "B62qnqEqsuH7kST9ZrbksRzihXD2tgHfvq9TF73XKAMj47gisT9xsJ5",
+                "B62qnsHmPQpZSKnrp978ZHFYwCJFBZtY1qE3UD97dd7taQarEV6ZpuG",
+                16640000000000,
+                200100000,
+                1,
+                "7mX2BWdRnVDRrdhneyhrD5by7F2Xe1d9oEPerkC1qtr3fLXHRquaLE1FtT8cvLapqbcpEGNzUqxedgJ9bzeeYVLNGrdiBsAD",
+            ),
+            (
+                "B62qrRvo5wngd5WA1dgXkQpCdQMRDndusmjfWXWT1LgsSFFdBS9RCsV",
+                "B62qqo8Skx1ys3HFVj3owPCgavmUJSNiuhSd1NnBdhEFc7ZcyGMAt4C",
+                9910579000000,
+                15000000,
+                65482,
+                "7mXJLLXuxfg5RkyvqxdJMSiHFnzYtJvXwoAfiN4xdK6LfGrr9VdU48a1WQeuEyv6Rt35VQVVAhAbzoMUdCe6NJ37nTFhHPXc",
+            ),
+        ] {
+            let (payload, signature, signer) = mainnet_payment_reference(
+                source, receiver, amount, fee, nonce, 4294967295, signature,
+            );
+
+            assert_eq!(read_roinput_bits(&payload, amount_offset(&payload), 64), amount);
+            assert_byte_order_is_signed(&payload, &signature, &signer, |p| match &mut p.body {
+                SignedCommandPayloadBody::PaymentPayload(pp) => {
+                    pp.amount.0 = pp.amount.0.swap_bytes()
+                }
+                _ => unreachable!("not a payment"),
+            });
+        }
+    }
+
+    #[test]
+    fn reference_vectors_valid_until_byte_order() {
+        // Every mainnet command of the fixtures is valid until u32::MAX, whose bytes read the
+        // same in either order, so this uses the inputs of sign_payment_test_1 instead
+        let (payload, kp) = test_util::payment_test_1();
+        let signature = Signature::from_hex("124c592178ed380cdffb11a9f8e1521bf940e39c13f37ba4c55bb4454ea69fba3c3595a55b06dac86261bb8ab97126bf3f7fff70270300cb97ff41401a5ef789").unwrap();
+        let signer = kp.public;
+
+        assert_eq!(read_roinput_bits(&payload, VALID_UNTIL_OFFSET, 32), 271828);
+        assert_byte_order_is_signed(&payload, &signature, &signer, |p| {
+            p.common.valid_until.0 = p.common.valid_until.0.swap_bytes()
+        });
+    }
+
+    #[test]
+    fn sign_with_secret_key_strings() -> Result<(), SecretKeyError> {
+        let (payload, _) = test_util::payment_test_1();
+        let sk_hex = "164244176fddb5d769b7de2027469d027ad428fadcc0c02396e6280142efb718";
+        let sk_base58 = "EKDt66ubGg5SDiwcQABWfFZaruq6idcyrLLfyZQjoH4CN3PHEiNj";
+
+        let from_hex =
+            SignedCommand::from_payload_with_hex_sk(payload.clone(), sk_hex, NetworkId::MAINNET)?;
+        let from_base58 = SignedCommand::from_payload_with_base58_sk(
+            payload.clone(),
+            sk_base58,
+            NetworkId::MAINNET,
+        )?;
+        assert_eq!(from_hex, from_base58);
+        assert_eq!(
+            from_hex,
+            SignedCommand::from_payload(
+                payload.clone(),
+                Keypair::from_hex(sk_hex).unwrap(),
+                NetworkId::MAINNET
+            )
+        );
+        // The reference vector is a legacy signature
+        if SignatureScheme::DEFAULT == SignatureScheme::Legacy {
+            assert_eq!(
+                from_hex.signature.to_string(),
+                "124c592178ed380cdffb11a9f8e1521bf940e39c13f37ba4c55bb4454ea69fba3c3595a55b06dac86261bb8ab97126bf3f7fff70270300cb97ff41401a5ef789"
+            );
+        }
+
+        // Hex is not valid base58, it may contain zeros
+        assert!(matches!(
+            SignedCommand::from_payload_with_base58_sk(payload, sk_hex, NetworkId::MAINNET),
+            Err(SecretKeyError::InvalidBase58(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn transaction_id_ignores_signature() {
+        let (payload, kp) = test_util::payment_test_1();
+        let payment = |nonce: u32| {
+            let mut payload = payload.clone();
+            payload.common.nonce = AccountNonce(nonce);
+            payload
+        };
+        let sign = |scheme| {
+            SignedCommand::from(NetworkSignedCommand::from_payload(
+                payment(16),
+                kp.clone(),
+                NetworkId::MAINNET,
+                scheme,
+            ))
+        };
+
+        let legacy = sign(SignatureScheme::Legacy);
+        let kimchi = sign(SignatureScheme::Kimchi);
+        assert_ne!(legacy.signature, kimchi.signature);
+        assert_eq!(legacy.transaction_id(), kimchi.transaction_id());
+        assert_eq!(
+            legacy.transaction_id(),
+            sign_legacy(payment(16), kp.clone(), NetworkId::TESTNET).transaction_id()
+        );
+
+        let other = sign_legacy(payment(17), kp.clone(), NetworkId::MAINNET);
+        assert_ne!(legacy.transaction_id(), other.transaction_id());
+        assert!(bs58::decode(legacy.transaction_id())
+            .with_check(None)
+            .into_vec()
+            .is_ok());
+    }
+
+    #[test]
+    fn network_signed_command_verifies_with_carried_context() {
+        let (payload, kp) = test_util::payment_test_1();
+
+        for scheme in [SignatureScheme::Legacy, SignatureScheme::Kimchi] {
+            for network in [NetworkId::TESTNET, NetworkId::MAINNET] {
+                let cmd = NetworkSignedCommand::from_payload(
+                    payload.clone(),
+                    kp.clone(),
+                    network,
+                    scheme,
+                );
+                assert!(cmd.verify());
+
+                // The same signature under the other network or scheme is rejected
+                let mut wrong_network = cmd.clone();
+                wrong_network.network = match cmd.network {
+                    NetworkId::MAINNET => NetworkId::TESTNET,
+                    NetworkId::TESTNET => NetworkId::MAINNET,
+                };
+                assert!(!wrong_network.verify());
+                let mut wrong_scheme = cmd.clone();
+                wrong_scheme.scheme = match cmd.scheme {
+                    SignatureScheme::Legacy => SignatureScheme::Kimchi,
+                    SignatureScheme::Kimchi => SignatureScheme::Legacy,
+                };
+                assert!(!wrong_scheme.verify());
+            }
+        }
+
+        // Signing through the wrapper with the default scheme matches from_payload
+        let cmd = NetworkSignedCommand::from_payload(
+            payload.clone(),
+            kp.clone(),
+            NetworkId::MAINNET,
+            SignatureScheme::default(),
+        );
+        assert_eq!(
+            SignedCommand::from(cmd),
+            SignedCommand::from_payload(payload, kp, NetworkId::MAINNET)
+        );
+    }
+
+    #[test]
+    fn signed_command_hash_set_dedup() {
+        let (payload, kp) = test_util::payment_test_1();
+        let payment = |nonce: u32| {
+            let mut payload = payload.clone();
+            payload.common.nonce = AccountNonce(nonce);
+            sign_legacy(payload, kp.clone(), NetworkId::MAINNET)
+        };
+        let delegation = builder::SignedDelegationCommandBuilder::new(
+            kp.public.into_compressed(),
+            CompressedPubKey::from_address(
+                "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+            )
+            .unwrap(),
+            2000000000,
+            0,
+        )
+        .build();
+
+        let mut mempool = std::collections::HashSet::new();
+        assert!(mempool.insert(payment(0)));
+        assert!(mempool.insert(payment(1)));
+        assert!(mempool.insert(sign_legacy(
+            delegation.clone(),
+            kp.clone(),
+            NetworkId::MAINNET
+        )));
+        // Signing is deterministic so these are equal to the commands already inserted
+        assert!(!mempool.insert(payment(0)));
+        assert!(!mempool.insert(sign_legacy(delegation, kp.clone(), NetworkId::MAINNET)));
+        // Same payload signed for another network differs in signature only
+        assert!(mempool.insert(sign_legacy(
+            payment(0).payload,
+            kp.clone(),
+            NetworkId::TESTNET
+        )));
+        assert_eq!(mempool.len(), 4);
+    }
+
+    #[test]
+    fn signed_command_common_accessors() {
+        let (payload, kp) = test_util::payment_test_1();
+        let cmd = sign_legacy(payload, kp, NetworkId::TESTNET);
+
+        assert_eq!(cmd.fee(), Amount(2000000000));
+        assert_eq!(cmd.fee_token(), TokenId::default_token());
+        assert_eq!(cmd.nonce(), AccountNonce(16));
+        assert_eq!(cmd.valid_until(), GlobalSlotNumber(271828));
+    }
+
+    #[test]
+    fn signed_command_memo() {
+        let (payload, kp) = test_util::payment_test_1();
+        let cmd = sign_legacy(payload, kp.clone(), NetworkId::MAINNET);
+        assert_eq!(
+            cmd.memo(),
+            &SignedCommandMemo::try_from_text("Hello Mina!").unwrap()
+        );
+        assert_eq!(cmd.memo_text(), Some("Hello Mina!".into()));
+
+        // The builder default memo is the empty memo
+        let pk = kp.public.into_compressed();
+        let default_memo =
+            builder::SignedTransferCommandBuilder::new(pk.clone(), pk, 1, 1, 0).build();
+        let cmd = sign_legacy(default_memo, kp, NetworkId::MAINNET);
+        assert_eq!(cmd.memo(), &SignedCommandMemo::empty());
+        assert_eq!(cmd.memo_text(), Some("".into()));
+    }
+
+    #[test]
+    fn signed_command_mempool_ordering() {
+        let (alice, bob) = test_util::two_senders();
+        let payment = test_util::mainnet_payment;
+
+        let mut commands = vec![
+            payment(&alice, 30, 0),
+            payment(&bob, 10, 0),
+            payment(&alice, 20, 1),
+            // Replaces the first alice command with a higher fee
+            payment(&alice, 40, 0),
+        ];
+        commands.sort_by(|a, b| b.cmp_fee_rate(a));
+        let fees: Vec<u64> = commands.iter().map(|c| c.payload.common.fee.0).collect();
+        assert_eq!(fees, vec![40, 30, 20, 10]);
+
+        // Keep the highest fee command for each sender and nonce
+        let mut slots: Vec<&SignedCommand> = Vec::new();
+        for cmd in commands.iter() {
+            if !slots.iter().any(|c| c.mempool_key() == cmd.mempool_key()) {
+                slots.push(cmd);
+            }
+        }
+        let keys: Vec<_> = slots.iter().map(|c| c.mempool_key()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                (alice.public.into_compressed(), AccountNonce(0)),
+                (alice.public.into_compressed(), AccountNonce(1)),
+                (bob.public.into_compressed(), AccountNonce(0)),
+            ]
+        );
+        assert_eq!(slots[0].payload.common.fee, Amount(40));
+    }
+
+    #[test]
+    fn same_payload_ignores_signature() {
+        let (payload, kp) = test_util::payment_test_1();
+        let cmd = sign_legacy(payload.clone(), kp.clone(), NetworkId::MAINNET);
+        let resigned = SignedCommand {
+            signature: sign_legacy(payload.clone(), kp, NetworkId::TESTNET).signature,
+            ..cmd.clone()
+        };
+        assert_ne!(cmd.signature, resigned.signature);
+        assert_ne!(cmd, resigned);
+        assert!(cmd.same_payload(&resigned));
+        assert!(resigned.same_payload(&cmd));
+
+        let other_signer = SignedCommand {
+            signer: CompressedPubKey::from_address(
+                "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+            )
+            .unwrap(),
+            ..cmd.clone()
+        };
+        assert!(!cmd.same_payload(&other_signer));
+
+        let mut other_payload = cmd.clone();
+        other_payload.payload.common.nonce = AccountNonce(17);
+        assert!(!cmd.same_payload(&other_payload));
+    }
+
+    #[test]
+    fn group_commands_by_fee_payer() {
+        let (alice, bob) = test_util::two_senders();
+        let payment = test_util::mainnet_payment;
+
+        let groups = group_by_fee_payer(vec![
+            payment(&alice, 10, 3),
+            payment(&bob, 10, 1),
+            payment(&alice, 10, 0),
+            payment(&bob, 10, 0),
+            payment(&alice, 20, 3),
+            payment(&alice, 10, 1),
+        ]);
+        assert_eq!(groups.len(), 2);
+
+        let summary = |kp: &Keypair| -> Vec<(u32, u64)> {
+            groups[&OrdPubKey(kp.public.into_compressed())]
+                .iter()
+                .map(|c| (c.nonce().0, c.fee().0))
+                .collect()
+        };
+        // Sorted by nonce, the replacement at nonce 3 stays after the original
+        assert_eq!(summary(&alice), vec![(0, 10), (1, 10), (3, 10), (3, 20)]);
+        assert_eq!(summary(&bob), vec![(0, 10), (1, 10)]);
+    }
+
+    #[test]
+    fn detect_nonce_gaps_in_batch() {
+        let (payload, kp) = test_util::payment_test_1();
+        let with_nonce = |nonce| {
+            let mut payload = payload.clone();
+            payload.common.nonce = AccountNonce(nonce);
+            sign_legacy(payload, kp.clone(), NetworkId::MAINNET)
+        };
+        let cmds: Vec<_> = [6, 8, 5, 6].into_iter().map(with_nonce).collect();
+
+        assert_eq!(
+            detect_nonce_gaps(&cmds, AccountNonce(5)),
+            vec![AccountNonce(7)]
+        );
+        assert_eq!(
+            detect_nonce_gaps(&cmds, AccountNonce(3)),
+            vec![AccountNonce(3), AccountNonce(4), AccountNonce(7)]
+        );
+        // Commands below the account nonce are already applied
+        assert_eq!(
+            detect_nonce_gaps(&cmds, AccountNonce(6)),
+            vec![AccountNonce(7)]
+        );
+        assert!(detect_nonce_gaps(&cmds[..1], AccountNonce(6)).is_empty());
+        assert!(detect_nonce_gaps(&cmds, AccountNonce(9)).is_empty());
+    }
+
+    #[test]
+    fn delegation_from_graphql_json() -> anyhow::Result<()> {
+        // Stake delegation at nonce 4 of block 147571 of mainnet, in the shape of the node's
+        // graphql with the new delegate as receiver and no amount
+        let json = serde_json::json!({
+            "kind": "STAKE_DELEGATION",
+            "isDelegation": true,
+            "nonce": 4,
+            "feePayer": { "publicKey": "B62qkPv5qdMBHRcZtPXJEegRvEE9aLwNsyBx4ZZAQ94XKixJNdD26aY" },
+            "source": { "publicKey": "B62qkPv5qdMBHRcZtPXJEegRvEE9aLwNsyBx4ZZAQ94XKixJNdD26aY" },
+            "receiver": { "publicKey": "B62qjSytpSK7aEauBprjXDSZwc9ai4YMv9tpmXLQK14Vy941YV36rMz" },
+            "fee": "10100000",
+            "feeToken": "1",
+            "amount": null,
+            "token": "1",
+            "validUntil": "4294967295",
+            "memo": "E4YM2vTHhWEg66xpj52JErHUBU4pZ1yageL4TVDDpTTSsv8mK6YaH",
+            "signer": { "publicKey": "B62qkPv5qdMBHRcZtPXJEegRvEE9aLwNsyBx4ZZAQ94XKixJNdD26aY" },
+            "signature": "7mXSENtPjoNij4o3XruxLw4BbY7qogNkyGtHWhu8ffnYDpqHDE3rbiydfgYDiCpgq54AviMfxJfdPTyYRcJSobNan2FWnzD2",
+        });
+        let cmd = SignedCommand::from_graphql_json(&json)?;
+        let delegation = DelegationCommand::try_from(cmd.clone())?;
+        assert_eq!(
+            delegation.delegate().into_address(),
+            "B62qjSytpSK7aEauBprjXDSZwc9ai4YMv9tpmXLQK14Vy941YV36rMz"
+        );
+        assert_eq!(delegation.delegator(), &cmd.signer);
+        assert_eq!(cmd.fee(), Amount(10100000));
+        assert_eq!(cmd.nonce(), AccountNonce(4));
+        assert!(NetworkSignedCommand {
+            command: cmd.clone(),
+            network: NetworkId::MAINNET,
+            scheme: SignatureScheme::Legacy,
+        }
+        .verify());
+
+        // The zero amount we emit for delegations is ignored the same way
+        assert_eq!(
+            SignedCommand::from_graphql_json(&cmd.to_graphql_json())?,
+            cmd
+        );
+
+        // Payments do need an amount
+        let mut payment = json.clone();
+        payment["kind"] = "PAYMENT".into();
+        assert!(SignedCommand::from_graphql_json(&payment).is_err());
+        payment["amount"] = "0".into();
+        assert!(PaymentCommand::try_from(SignedCommand::from_graphql_json(&payment)?).is_ok());
+
+        let mut unknown = json;
+        unknown["kind"] = "MINT_TOKENS".into();
+        assert!(SignedCommand::from_graphql_json(&unknown).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn payment_graphql_json_roundtrip() -> anyhow::Result<()> {
+        let (payload, kp) = test_util::payment_test_1();
+        let cmd = sign_legacy(payload, kp, NetworkId::TESTNET);
+        assert_eq!(
+            SignedCommand::from_graphql_json(&cmd.to_graphql_json())?,
+            cmd
+        );
+
+        // The signature is also accepted in its object form
+        let mut json = cmd.to_graphql_json();
+        json["signature"] = cmd.signature.to_json_object();
+        assert_eq!(SignedCommand::from_graphql_json(&json)?, cmd);
+        Ok(())
+    }
+
+    #[test]
+    fn verify_any_scheme_accepts_both_schemes() {
+        let (payload, kp) = test_util::payment_test_1();
+        for scheme in [SignatureScheme::Legacy, SignatureScheme::Kimchi] {
+            let cmd: SignedCommand = NetworkSignedCommand::from_payload(
+                payload.clone(),
+                kp.clone(),
+                NetworkId::TESTNET,
+                scheme,
+            )
+            .into();
+            assert!(cmd.verify_any_scheme(NetworkId::TESTNET));
+            assert!(!cmd.verify_any_scheme(NetworkId::MAINNET));
+
+            let mut tampered = cmd.clone();
+            tampered.payload.common.nonce = AccountNonce(17);
+            assert!(!tampered.verify_any_scheme(NetworkId::TESTNET));
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing_spans_around_sign_and_verify() {
+        use std::fmt::Debug;
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        /// Records the name and fields of every new span
+        #[derive(Default)]
+        struct SpanRecorder(Arc<Mutex<Vec<String>>>);
+
+        struct FieldsVisitor(String);
+
+        impl Visit for FieldsVisitor {
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                self.0 += &format!(" {}={:?}", field.name(), value);
+            }
+        }
+
+        impl tracing::Subscriber for SpanRecorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut visitor = FieldsVisitor(span.metadata().name().into());
+                span.record(&mut visitor);
+                let mut spans = self.0.lock().unwrap();
+                spans.push(visitor.0);
+                Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, _: &Event<'_>) {}
+
+            fn enter(&self, _: &Id) {}
+
+            fn exit(&self, _: &Id) {}
+        }
+
+        let (payload, kp) = test_util::payment_test_1();
+        let recorder = SpanRecorder::default();
+        let spans = recorder.0.clone();
+        tracing::subscriber::with_default(recorder, || {
+            let cmd = NetworkSignedCommand::from_payload(
+                payload,
+                kp,
+                NetworkId::TESTNET,
+                SignatureScheme::DEFAULT,
+            );
+            assert!(cmd.verify());
+        });
+
+        let spans = spans.lock().unwrap();
+        assert_eq!(spans.len(), 2);
+        for (span, name) in spans.iter().zip(["sign_command", "verify_command"]) {
+            assert!(span.starts_with(name), "{span}");
+            assert!(span.contains("network=TESTNET"), "{span}");
+            assert!(
+                span.contains("signer=B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV"),
+                "{span}"
+            );
+            assert!(span.contains("kind=\"payment\""), "{span}");
+        }
+    }
+
+    #[test]
+    fn json_pretty_matches_graphql_json() -> anyhow::Result<()> {
+        let (payload, kp) = test_util::payment_test_1();
+        let cmd = SignedCommand::from_payload(payload, kp, NetworkId::TESTNET);
+        let pretty = cmd.to_json_pretty();
+        assert!(pretty.contains("\n  \"kind\": \"PAYMENT\""));
+
+        let compact = serde_json::to_string(&cmd.to_graphql_json())?;
+        assert_ne!(pretty, compact);
+        let parsed: serde_json::Value = serde_json::from_str(&pretty)?;
+        assert_eq!(parsed, serde_json::from_str::<serde_json::Value>(&compact)?);
+        assert_eq!(parsed, cmd.to_graphql_json());
+        Ok(())
+    }
+
+    #[test]
+    fn summary_is_human_readable() {
+        let (payload, kp) = test_util::payment_test_1();
+        let cmd = sign_legacy(payload, kp.clone(), NetworkId::TESTNET);
+        assert_eq!(
+            cmd.summary(),
+            format!(
+                "payment B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV -> \
+                 B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt amount 1729.000000000 \
+                 fee 2.000000000 nonce 16 signature {}...",
+                &cmd.signature.to_string()[..16]
+            )
+        );
+
+        let delegation = sign_legacy(
+            builder::SignedDelegationCommandBuilder::new(
+                kp.public.into_compressed(),
+                CompressedPubKey::from_address(
+                    "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+                )
+                .unwrap(),
+                10100000,
+                4,
+            )
+            .build(),
+            kp,
+            NetworkId::TESTNET,
+        );
+        let summary = delegation.summary();
+        assert!(summary.starts_with(
+            "stake delegation B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV -> \
+             B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt fee 0.010100000 nonce 4"
+        ));
+        assert!(!summary.contains("amount"));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn sign_rejects_zero_public_key() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let (mut payload, kp) = test_util::payment_test_1();
+        if let SignedCommandPayloadBody::PaymentPayload(pp) = &mut payload.body {
+            pp.receiver_pk = CompressedPubKey {
+                x: Fp::zero(),
+                is_odd: false,
+            };
+        }
+        let sign_paths: [&dyn Fn(SignedCommandPayload) -> SignedCommand; 2] = [
+            &|payload| SignedCommand::from_payload(payload, kp.clone(), NetworkId::TESTNET),
+            &|payload| {
+                SignedCommand::from_payload_with_domain(
+                    payload,
+                    &kp,
+                    &NetworkId::TESTNET.into(),
+                    SignatureScheme::Legacy,
+                )
+            },
+        ];
+        for sign in sign_paths {
+            let payload = payload.clone();
+            let panic = catch_unwind(AssertUnwindSafe(|| sign(payload)))
+                .expect_err("signed a payload with a zero public key");
+            assert_eq!(
+                panic.downcast_ref::<String>().map(String::as_str),
+                Some("The receiver public key is zero")
+            );
+        }
+    }
+
+    #[test]
+    fn signer_address_of_payment_test_1() {
+        let (payload, kp) = test_util::payment_test_1();
+        let cmd = SignedCommand::from_payload(payload, kp, NetworkId::TESTNET);
+        // Source address of sign_payment_test_1, which signs its own payment
+        assert_eq!(
+            cmd.signer_address(),
+            "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV"
+        );
+    }
+
+    #[test]
+    fn sign_both_networks() {
+        let (payload, kp) = test_util::payment_test_1();
+        let (testnet, mainnet) = payload.sign_both(&kp);
+        assert_eq!(
+            testnet,
+            SignedCommand::from_payload(payload.clone(), kp.clone(), NetworkId::TESTNET)
+        );
+        assert_eq!(
+            mainnet,
+            SignedCommand::from_payload(payload.clone(), kp, NetworkId::MAINNET)
+        );
+        assert_ne!(testnet.signature, mainnet.signature);
+        assert!(testnet.same_payload(&mainnet));
+
+        for (cmd, network, other) in [
+            (&testnet, NetworkId::TESTNET, NetworkId::MAINNET),
+            (&mainnet, NetworkId::MAINNET, NetworkId::TESTNET),
+        ] {
+            assert!(verify_signature(
+                &payload,
+                &cmd.signer,
+                &cmd.signature,
+                network
+            ));
+            assert!(!verify_signature(
+                &payload,
+                &cmd.signer,
+                &cmd.signature,
+                other
+            ));
+        }
+    }
+
+    #[test]
+    fn validate_reports_every_issue() {
+        let (payload, kp) = test_util::payment_test_1();
+        let cmd = sign_legacy(payload, kp, NetworkId::TESTNET);
+        let mut testnet_ctx =
+            mina_signer::create_legacy::<SignedCommandPayload>(NetworkId::TESTNET);
+        let mut mainnet_ctx =
+            mina_signer::create_legacy::<SignedCommandPayload>(NetworkId::MAINNET);
+        let nonce = AccountNonce(16);
+        let slot = GlobalSlotNumber(271828);
+
+        assert_eq!(cmd.validate(&mut testnet_ctx, nonce, slot), Ok(()));
+        assert_eq!(
+            cmd.validate(&mut mainnet_ctx, nonce, slot),
+            Err(vec![ValidationIssue::InvalidSignature])
+        );
+        assert_eq!(
+            cmd.validate(&mut testnet_ctx, AccountNonce(15), slot),
+            Err(vec![ValidationIssue::NonceMismatch {
+                expected: AccountNonce(15),
+                found: nonce,
+            }])
+        );
+        assert_eq!(
+            cmd.validate(&mut testnet_ctx, nonce, GlobalSlotNumber(271829)),
+            Err(vec![ValidationIssue::Expired {
+                valid_until: slot,
+                current_slot: GlobalSlotNumber(271829),
+            }])
+        );
+        assert_eq!(
+            cmd.validate(&mut mainnet_ctx, AccountNonce(17), GlobalSlotNumber(271829))
+                .unwrap_err()
+                .len(),
+            3
+        );
+
+        let (mut payload, kp) = test_util::payment_test_1();
+        payload.common.fee_token = TokenId(2);
+        let cmd = sign_legacy(payload, kp, NetworkId::TESTNET);
+        assert_eq!(
+            cmd.validate(&mut testnet_ctx, nonce, slot),
+            Err(vec![ValidationIssue::Tokens(
+                TokenError::NonDefaultFeeToken(TokenId(2))
+            )])
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "kimchi-signatures"))]
+    fn from_payload_defaults_to_legacy() {
+        assert_eq!(SignatureScheme::DEFAULT, SignatureScheme::Legacy);
+        let (payload, kp) = test_util::payment_test_1();
+        let cmd = SignedCommand::from_payload(payload, kp, NetworkId::MAINNET);
+        assert!(
+            cmd.verify(&mut mina_signer::create_legacy::<SignedCommandPayload>(
+                NetworkId::MAINNET
+            ))
+        );
+        assert!(
+            !cmd.verify(&mut mina_signer::create_kimchi::<SignedCommandPayload>(
+                NetworkId::MAINNET
+            ))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "kimchi-signatures")]
+    fn from_payload_defaults_to_kimchi() {
+        assert_eq!(SignatureScheme::DEFAULT, SignatureScheme::Kimchi);
+        let (payload, kp) = test_util::payment_test_1();
+        let cmd = SignedCommand::from_payload(payload, kp, NetworkId::MAINNET);
+        assert!(
+            cmd.verify(&mut mina_signer::create_kimchi::<SignedCommandPayload>(
+                NetworkId::MAINNET
+            ))
+        );
+        assert!(
+            !cmd.verify(&mut mina_signer::create_legacy::<SignedCommandPayload>(
+                NetworkId::MAINNET
+            ))
+        );
+    }
+
+    #[test]
+    fn cached_signer_context_matches_fresh_context() {
+        let (mut payload, kp) = test_util::payment_test_1();
+        for network in [NetworkId::TESTNET, NetworkId::MAINNET] {
+            // Reusing the cached context must not carry hasher state between commands
+            for nonce in 0..4 {
+                payload.common.nonce = AccountNonce(nonce);
+                let cached = sign_legacy(payload.clone(), kp.clone(), network.clone());
+                let mut ctx = proof_systems::mina_signer::create_legacy::<SignedCommandPayload>(
+                    network.clone(),
+                );
+                assert_eq!(cached.signature, ctx.sign(&kp, &payload));
+                assert!(cached.verify(&mut ctx));
+            }
+        }
+    }
+
+    #[test]
+    fn roinput_fields_snapshot_payment_test_1() {
+        let (payload, _) = test_util::payment_test_1();
+
+        // Field elements in little-endian hex: fee payer x, source x, receiver x,
+        // followed by the packed bits (fee, fee token, fee payer parity, nonce, valid until,
+        // memo, tag, source parity, receiver parity, token id, amount, token locked)
+        let fields: Vec<String> = payload
+            .to_roinput_fields()
+            .iter()
+            .map(|f| hex::encode(f.to_bytes()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                "876fc2293ca688421553de93b14e8b25d08c7a9cb0d0ebc0ae19a78b3e1a4a1c",
+                "876fc2293ca688421553de93b14e8b25d08c7a9cb0d0ebc0ae19a78b3e1a4a1c",
+                "04d5806fee539e9190827496c9c07dd249f775785e0baf8155d9ba9ab0466f2b",
+                "0094357700000000010000000000000021000000a84b0800021690cad8d8de00",
+                "694a730b0b010000000000000000000000000000000000000000400100000000",
+                "0000000028b9414a060000000000000000000000000000000000000000000000",
+            ]
+        );
+        assert_eq!(payload.to_roinput_bytes().len(), (3 * 255 + 599 + 7) / 8);
+    }
+
+    #[test]
+    fn valid_until_expiry() {
+        let pk = CompressedPubKey::from_address(
+            "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+        )
+        .unwrap();
+        let forever = builder::SignedTransferCommandBuilder::new(pk.clone(), pk.clone(), 1, 1, 0)
+            .valid_until(GlobalSlotNumber::forever())
+            .build();
+        assert!(forever.common.valid_until.is_forever());
+        assert!(!forever.common.is_expired(GlobalSlotNumber(0)));
+        assert!(!forever.common.is_expired(GlobalSlotNumber::MAX));
+
+        let limited = builder::SignedTransferCommandBuilder::new(pk.clone(), pk, 1, 1, 0)
+            .valid_until(1982)
+            .build();
+        assert!(!limited.common.valid_until.is_forever());
+        assert!(!limited.common.is_expired(GlobalSlotNumber(1981)));
+        assert!(!limited.common.is_expired(GlobalSlotNumber(1982)));
+        assert!(limited.common.is_expired(GlobalSlotNumber(1983)));
+    }
+
+    fn mainnet_delegation_147571() -> SignedCommandPayload {
+        // Stake delegation taken from mainnet block 3NKwrze6FvGQCCF6L7Q2JLvwgnsm56hwSny9kUyjbSUr8oqu1MGp
+        let delegator = CompressedPubKey::from_address(
+            "B62qkPv5qdMBHRcZtPXJEegRvEE9aLwNsyBx4ZZAQ94XKixJNdD26aY",
+        )
+        .unwrap();
+        SignedCommandPayload {
+            common: SignedCommandPayloadCommon {
+                fee: Amount(10100000),
+                fee_token: TokenId(1),
+                fee_payer_pk: delegator.clone(),
+                nonce: AccountNonce(4),
+                valid_until: GlobalSlotNumber::MAX,
+                memo: SignedCommandMemo::try_from_text("").unwrap(),
+            },
+            body: SignedCommandPayloadBody::StakeDelegation(StakeDelegation::SetDelegate {
+                delegator,
+                new_delegate: CompressedPubKey::from_address(
+                    "B62qjSytpSK7aEauBprjXDSZwc9ai4YMv9tpmXLQK14Vy941YV36rMz",
+                )
+                .unwrap(),
+            }),
+        }
+    }
+
+    #[test]
+    fn roinput_fields_snapshot_delegation_non_default_fee_token() {
+        let mut payload = mainnet_delegation_147571();
+        payload.common.fee_token = TokenId(2);
+
+        let fields: Vec<String> = payload
+            .to_roinput_fields()
+            .iter()
+            .map(|f| hex::encode(f.to_bytes()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                "39ebc65c6546bc9456b7a473886c7bf3998db212070f4c3128828fe77d46c027",
+                "39ebc65c6546bc9456b7a473886c7bf3998db212070f4c3128828fe77d46c027",
+                "1da9240201852e0b13dad64203459af131786baf15d75640704e3bb7d1abf017",
+                "201d9a0000000000020000000000000008000000feffffff0300000000000000",
+                "0000000000000000000000000000000000000000000000000000200100000000",
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ]
+        );
+    }
+
+    #[test]
+    fn roinput_max_u64_packs_exactly() {
+        let payment = builder::SignedTransferCommandBuilder::new(
+            CompressedPubKey::from_address(
+                "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+            )
+            .unwrap(),
+            CompressedPubKey::from_address(
+                "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+            )
+            .unwrap(),
+            u64::MAX,
+            u64::MAX,
+            16,
+        )
+        .build();
+        let bytes = payment.to_roinput_bytes();
+        let bit = |i: usize| (bytes[i / 8] >> (i % 8)) & 1 == 1;
+        let read_u64 =
+            |offset: usize| (0..64).fold(0u64, |acc, i| acc | (bit(offset + i) as u64) << i);
+
+        // After the three 255 bit field elements
+        let fee_offset = 3 * 255;
+        assert_eq!(read_u64(fee_offset), u64::MAX);
+        assert_eq!(read_u64(fee_offset + 64), 1);
+        // The amount is followed only by the token locked bit
+        let amount_offset = payment.roinput_bit_len() - 1 - 64;
+        assert_eq!(read_u64(amount_offset), u64::MAX);
+        assert_eq!(read_u64(amount_offset - 64), 1);
+        assert!(!bit(amount_offset + 64));
+    }
+
+    #[test]
+    fn roinput_bit_len_is_stable() {
+        // 3 field elements of 255 bits, 193 common bits, a 34 byte memo, 3 tag bits and 131
+        // body bits
+        let (payment, _) = test_util::payment_test_1();
+        assert_eq!(payment.roinput_bit_len(), 1364);
+
+        let delegation = mainnet_delegation_147571();
+        assert_eq!(delegation.roinput_bit_len(), 1364);
+
+        // The packed bits take up 254 bits of each trailing field element
+        for payload in [&payment, &delegation] {
+            let packed_bits = payload.roinput_bit_len() - 3 * 255;
+            assert_eq!(
+                payload.to_roinput_fields().len(),
+                3 + (packed_bits + 253) / 254
+            );
+        }
+    }
+
+    #[test]
+    fn signed_command_check_network() {
+        let (payload, kp) = test_util::payment_test_1();
+
+        for scheme in [SignatureScheme::Legacy, SignatureScheme::Kimchi] {
+            for network in [NetworkId::TESTNET, NetworkId::MAINNET] {
+                let cmd: SignedCommand = NetworkSignedCommand::from_payload(
+                    payload.clone(),
+                    kp.clone(),
+                    network.clone(),
+                    scheme,
+                )
+                .into();
+                assert!(cmd.check_network(network.clone(), scheme).is_ok());
+
+                // Expecting the other network reports which one the command is signed for
+                let other = match network {
+                    NetworkId::MAINNET => NetworkId::TESTNET,
+                    NetworkId::TESTNET => NetworkId::MAINNET,
+                };
+                match cmd.check_network(other, scheme) {
+                    Err(NetworkCheckError::NetworkMismatch { found, .. }) => {
+                        assert_eq!(found as u8, network as u8)
+                    }
+                    res => panic!("Expected a network mismatch, got {res:?}"),
+                }
+            }
+        }
+
+        // A signature for neither network, here because the payload was altered after signing
+        let mut cmd = SignedCommand::from_payload(payload, kp, NetworkId::MAINNET);
+        cmd.payload.common.nonce = AccountNonce(17);
+        assert!(matches!(
+            cmd.check_network(NetworkId::MAINNET, SignatureScheme::default()),
+            Err(NetworkCheckError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn signed_command_serialized_size() {
+        let (payment, kp) = test_util::payment_test_1();
+        let delegation = mainnet_delegation_147571();
+
+        for payload in [payment, delegation] {
+            let cmd = SignedCommand::from_payload(payload, kp.clone(), NetworkId::MAINNET);
+            let size = cmd.serialized_size();
+            assert_eq!(size, cmd.try_into_binprot().unwrap().len());
+        }
+    }
+
+    #[test]
+    fn sign_ref_for_both_networks() {
+        let (payload, kp) = test_util::payment_test_1();
+
+        let mainnet_cmd = SignedCommand::sign_ref(&payload, &kp, NetworkId::MAINNET);
+        let testnet_cmd = SignedCommand::sign_ref(&payload, &kp, NetworkId::TESTNET);
+        assert_eq!(mainnet_cmd.payload, payload);
+        assert_eq!(testnet_cmd.payload, payload);
+        assert_ne!(mainnet_cmd.signature, testnet_cmd.signature);
+
+        let scheme = SignatureScheme::default();
+        assert!(mainnet_cmd
+            .check_network(NetworkId::MAINNET, scheme)
+            .is_ok());
+        assert!(testnet_cmd
+            .check_network(NetworkId::TESTNET, scheme)
+            .is_ok());
+
+        // Signing by reference matches the owning version
+        assert_eq!(
+            mainnet_cmd,
+            SignedCommand::from_payload(payload, kp, NetworkId::MAINNET)
+        );
+    }
+
+    #[test]
+    fn minimal_common_matches_builder_defaults() {
+        let source = CompressedPubKey::from_address(
+            "B62qnzbXmRNo9q32n4SNu2mpB8e7FYYLH8NmaX6oFCBYjjQ8SbD7uzV",
+        )
+        .unwrap();
+        let receiver = CompressedPubKey::from_address(
+            "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+        )
+        .unwrap();
+
+        let payment = builder::SignedTransferCommandBuilder::new(
+            source.clone(),
+            receiver.clone(),
+            1000,
+            0,
+            0,
+        )
+        .build();
+        assert_eq!(
+            payment,
+            SignedCommandPayload {
+                common: SignedCommandPayloadCommon::minimal(source.clone()),
+                body: SignedCommandPayloadBody::PaymentPayload(PaymentPayload::with_default_token(
+                    source.clone(),
+                    receiver.clone(),
+                    1000
+                )),
+            }
+        );
+
+        let delegation =
+            builder::SignedDelegationCommandBuilder::new(source.clone(), receiver, 0, 0).build();
+        assert_eq!(
+            delegation.common,
+            SignedCommandPayloadCommon::minimal(source)
+        );
+    }
+
+    #[test]
+    fn signed_command_is_nonce_valid() {
+        let (payload, kp) = test_util::payment_test_1();
+        let cmd = SignedCommand::from_payload(payload, kp, NetworkId::MAINNET);
+
+        assert!(cmd.is_nonce_valid(AccountNonce(16)));
+        // The account already used nonce 16
+        assert!(!cmd.is_nonce_valid(AccountNonce(17)));
+        // The account has not reached nonce 16 yet
+        assert!(!cmd.is_nonce_valid(AccountNonce(15)));
+    }
+
+    #[test]
+    fn roinput_bytes_golden() {
+        // The whole preimage as packed bytes: three 255 bit field elements followed by the
+        // packed bits, in append order. Any reordering of the appends changes these bytes
+        let (payment, _) = test_util::payment_test_1();
+        assert_eq!(
+            hex::encode(payment.to_roinput_bytes()),
+            concat!(
+                "876fc2293ca688421553de93b14e8b25d08c7a9cb0d0ebc0ae19a78b3e1a4a9c",
+                "c337e1141e5344a18a29efc958a7c51268463d4e58e87560d78cd3451f0d250e",
+                "4135e09bfb946724a4209d6532709f74d27d1d9ed7c26b6055b6ae26acd1db0a",
+                "80b2e60e0000002000000000000000200400000075090140c00252191bdb1b48",
+                "539a5b58080000000000000000000000000000000000000000000a0000000000",
+                "000000507283940c000000",
+            )
+        );
+
+        assert_eq!(
+            hex::encode(mainnet_delegation_147571().to_roinput_bytes()),
+            concat!(
+                "39ebc65c6546bc9456b7a473886c7bf3998db212070f4c3128828fe77d46c0a7",
+                "9c7563ae32235e4aab5bd23944b6bdf9cc4659898307a61814c1c7f33e23e053",
+                "472a894040a1cbc284b6b5d04091667c0cdeda6bc5b515109cd3ce6df42afc05",
+                "a4431300000000200000000000000000010000c0ffffff7f0000000000000000",
+                "0000000000000000000000000000000000000000000000000000090000000000",
+                "0000000000000000000000",
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_roundtrip_via_json() -> anyhow::Result<()> {
+        let (payload, kp) = test_util::payment_test_1();
+        let cmd = SignedCommand::from_payload(payload, kp, NetworkId::TESTNET);
+
+        let json = serde_json::to_string(&cmd)?;
+        // Same representation as the serialization types
+        assert_eq!(json, cmd.clone().try_into_json()?);
+        let recovered: SignedCommand = serde_json::from_str(&json)?;
+        assert_eq!(recovered, cmd);
+
+        let delegation = mainnet_delegation_147571();
+        let json = serde_json::to_string(&delegation)?;
+        assert_eq!(json, delegation.clone().try_into_json()?);
+        let recovered: SignedCommandPayload = serde_json::from_str(&json)?;
+        assert_eq!(recovered, delegation);
+        Ok(())
+    }
 }
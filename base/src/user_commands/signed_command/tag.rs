@@ -0,0 +1,72 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tags of the transaction union payload, which tell the kind of transaction in the
+//! random oracle input hashed for signatures
+
+use super::TAG_BITS;
+
+/// Kind of a transaction as encoded in its random oracle input.
+/// Only payments and stake delegations are implemented, the other values are reserved
+/// for the token commands and the internal commands
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum TransactionTag {
+    /// Payment
+    Payment = 0,
+    /// Stake delegation
+    StakeDelegation = 1,
+    /// Token account creation, reserved
+    CreateAccount = 2,
+    /// Token minting, reserved
+    MintTokens = 3,
+    /// Fee transfer, reserved
+    FeeTransfer = 4,
+    /// Coinbase, reserved
+    Coinbase = 5,
+}
+
+impl TransactionTag {
+    const ALL: [Self; 6] = [
+        Self::Payment,
+        Self::StakeDelegation,
+        Self::CreateAccount,
+        Self::MintTokens,
+        Self::FeeTransfer,
+        Self::Coinbase,
+    ];
+
+    /// The bits appended to the random oracle input, the tag value most significant bit first
+    pub const fn as_bits(self) -> [bool; TAG_BITS] {
+        let value = self as u8;
+        [value & 0b100 != 0, value & 0b010 != 0, value & 0b001 != 0]
+    }
+
+    /// The tag with the given bits, the inverse of [TransactionTag::as_bits].
+    /// None for the two bit patterns that are not a tag
+    pub fn from_bits(bits: [bool; TAG_BITS]) -> Option<Self> {
+        Self::ALL.into_iter().find(|tag| tag.as_bits() == bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{DELEGATION_TX_TAG, PAYMENT_TX_TAG};
+    use super::*;
+
+    #[test]
+    fn transaction_tag_bits() {
+        assert_eq!(TransactionTag::Payment.as_bits(), [false, false, false]);
+        assert_eq!(
+            TransactionTag::StakeDelegation.as_bits(),
+            [false, false, true]
+        );
+        assert_eq!(TransactionTag::Payment.as_bits(), PAYMENT_TX_TAG);
+        assert_eq!(TransactionTag::StakeDelegation.as_bits(), DELEGATION_TX_TAG);
+
+        for tag in TransactionTag::ALL {
+            assert_eq!(TransactionTag::from_bits(tag.as_bits()), Some(tag));
+        }
+        assert_eq!(TransactionTag::from_bits([true, true, false]), None);
+        assert_eq!(TransactionTag::from_bits([true, true, true]), None);
+    }
+}
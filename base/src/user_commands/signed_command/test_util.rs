@@ -0,0 +1,160 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fixtures and assertions shared by the tests of signed commands, including the checks
+//! against reference signature vectors
+
+use super::builder::SignedTransferCommandBuilder;
+use super::{
+    verify_signature, NetworkSignedCommand, SignatureScheme, SignedCommand, SignedCommandPayload,
+};
+use crate::signature::SignatureExt;
+use crate::user_commands::memo::SignedCommandMemo;
+use crate::verifiable::Verifiable;
+use proof_systems::mina_signer::{self, CompressedPubKey, Keypair, NetworkId, Signature, Signer};
+
+/// The payment of `sign_payment_test_1` and the keypair of its fee payer, for tests that
+/// need any signed payment: 1729 MINA from B62qnzbX... to B62qicip... with a fee of 2 MINA,
+/// nonce 16, valid until slot 271828 and the memo "Hello Mina!"
+pub(crate) fn payment_test_1() -> (SignedCommandPayload, Keypair) {
+    let keypair =
+        Keypair::from_hex("164244176fddb5d769b7de2027469d027ad428fadcc0c02396e6280142efb718")
+            .expect("failed to create keypair");
+    let payload = SignedTransferCommandBuilder::new(
+        keypair.public.into_compressed(),
+        CompressedPubKey::from_address("B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt")
+            .expect("invalid receiver address"),
+        1729000000000,
+        2000000000,
+        16,
+    )
+    .valid_until(271828)
+    .memo(SignedCommandMemo::try_from_text("Hello Mina!").expect("invalid memo string"))
+    .build();
+    (payload, keypair)
+}
+
+/// Two senders for tests over the commands of several accounts, the keypairs of
+/// `sign_payment_test_1` (B62qnzbX...) and `sign_payment_test_2` (B62qoG5Y...)
+pub(crate) fn two_senders() -> (Keypair, Keypair) {
+    let alice =
+        Keypair::from_hex("164244176fddb5d769b7de2027469d027ad428fadcc0c02396e6280142efb718")
+            .expect("failed to create keypair");
+    let bob = Keypair::from_hex("3414fc16e86e6ac272fda03cf8dcb4d7d47af91b4b726494dab43bf773ce1779")
+        .expect("failed to create keypair");
+    (alice, bob)
+}
+
+/// A payment of 1 MINA from the keypair to B62qicip... with the given fee and nonce,
+/// signed for mainnet with the legacy scheme
+pub(crate) fn mainnet_payment(keypair: &Keypair, fee: u64, nonce: u32) -> SignedCommand {
+    let payload = SignedTransferCommandBuilder::new(
+        keypair.public.into_compressed(),
+        CompressedPubKey::from_address("B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt")
+            .expect("invalid receiver address"),
+        1000000000,
+        fee,
+        nonce,
+    )
+    .build();
+    NetworkSignedCommand::from_payload(
+        payload,
+        keypair.clone(),
+        NetworkId::MAINNET,
+        SignatureScheme::Legacy,
+    )
+    .command
+}
+
+/// Sign the payload built by a command builder with the legacy scheme for both networks,
+/// and check the signatures against the testnet and mainnet vectors, given in hex form.
+/// Also checks that each signature only verifies on its own network and for that exact
+/// payload, through the signer contexts, [verify_signature] and [Verifiable::verify]
+pub(crate) fn assert_command_matches_vectors(
+    mut payload: SignedCommandPayload,
+    keypair: &Keypair,
+    testnet_target: &str,
+    mainnet_target: &str,
+) {
+    assert_eq!(
+        payload.common.fee_payer_pk,
+        keypair.public.into_compressed(),
+        "the vectors are signed by the fee payer"
+    );
+
+    let sign = |network| {
+        NetworkSignedCommand::from_payload(
+            payload.clone(),
+            keypair.clone(),
+            network,
+            SignatureScheme::Legacy,
+        )
+        .command
+    };
+    let testnet_cmd = sign(NetworkId::TESTNET);
+    let testnet_sig = &testnet_cmd.signature;
+    let mainnet_cmd = sign(NetworkId::MAINNET);
+    let mainnet_sig = &mainnet_cmd.signature;
+
+    // Context for verification
+    let mut testnet_ctx = mina_signer::create_legacy(NetworkId::TESTNET);
+    let mut mainnet_ctx = mina_signer::create_legacy(NetworkId::MAINNET);
+
+    // Signing checks
+    assert_ne!(testnet_sig, mainnet_sig);
+    assert_eq!(testnet_sig.to_string(), testnet_target);
+    assert_eq!(mainnet_sig.to_string(), mainnet_target);
+    assert_eq!(&Signature::from_hex(testnet_target).unwrap(), testnet_sig);
+    assert_eq!(&Signature::from_hex(mainnet_target).unwrap(), mainnet_sig);
+
+    // Verification checks
+    assert!(testnet_ctx.verify(testnet_sig, &keypair.public, &payload));
+    assert!(mainnet_ctx.verify(mainnet_sig, &keypair.public, &payload));
+
+    // Fails verification on the other network
+    assert!(!mainnet_ctx.verify(testnet_sig, &keypair.public, &payload));
+    assert!(!testnet_ctx.verify(mainnet_sig, &keypair.public, &payload));
+
+    // The free function uses the default scheme, which the vectors only match without the
+    // kimchi-signatures feature
+    if SignatureScheme::DEFAULT == SignatureScheme::Legacy {
+        let signer = keypair.public.into_compressed();
+        assert!(verify_signature(
+            &payload,
+            &signer,
+            testnet_sig,
+            NetworkId::TESTNET
+        ));
+        assert!(verify_signature(
+            &payload,
+            &signer,
+            mainnet_sig,
+            NetworkId::MAINNET
+        ));
+        assert!(!verify_signature(
+            &payload,
+            &signer,
+            testnet_sig,
+            NetworkId::MAINNET
+        ));
+        assert!(!verify_signature(
+            &payload,
+            &signer,
+            mainnet_sig,
+            NetworkId::TESTNET
+        ));
+    }
+
+    // Flip some bits, it should no longer pass verification
+    payload.common.valid_until.0 = !payload.common.valid_until.0;
+    assert!(!testnet_ctx.verify(testnet_sig, &keypair.public, &payload));
+    assert!(!mainnet_ctx.verify(mainnet_sig, &keypair.public, &payload));
+
+    // Also check using the implementation of verify
+    assert!(testnet_cmd.verify(&mut testnet_ctx));
+    assert!(mainnet_cmd.verify(&mut mainnet_ctx));
+
+    // Ensure they fail on the other network
+    assert!(!testnet_cmd.verify(&mut mainnet_ctx));
+    assert!(!mainnet_cmd.verify(&mut testnet_ctx));
+}
@@ -0,0 +1,195 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Signed commands narrowed down to one kind of body, for code that only handles that kind
+
+use super::{SignedCommand, SignedCommandPayloadBody, StakeDelegation};
+use crate::numbers::{Amount, TokenId};
+use crate::user_commands::PaymentPayload;
+use proof_systems::mina_signer::CompressedPubKey;
+use thiserror::Error;
+
+/// A signed command with a payment body
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct PaymentCommand(SignedCommand);
+
+impl PaymentCommand {
+    /// The payment body
+    pub fn payment(&self) -> &PaymentPayload {
+        match &self.0.payload.body {
+            SignedCommandPayloadBody::PaymentPayload(payment) => payment,
+            SignedCommandPayloadBody::StakeDelegation(_) => {
+                unreachable!("PaymentCommand is only built from payments")
+            }
+        }
+    }
+
+    /// Account to transfer from
+    pub fn source(&self) -> &CompressedPubKey {
+        self.payment().source_pk()
+    }
+
+    /// Account to transfer to
+    pub fn receiver(&self) -> &CompressedPubKey {
+        self.payment().receiver_pk()
+    }
+
+    /// The token to transfer
+    pub fn token_id(&self) -> &TokenId {
+        self.payment().token_id()
+    }
+
+    /// The amount of that token to transfer
+    pub fn amount(&self) -> Amount {
+        self.payment().amount()
+    }
+
+    /// The underlying signed command
+    pub fn command(&self) -> &SignedCommand {
+        &self.0
+    }
+}
+
+impl TryFrom<SignedCommand> for PaymentCommand {
+    type Error = CommandKindError;
+
+    fn try_from(command: SignedCommand) -> Result<Self, Self::Error> {
+        match command.payload.body {
+            SignedCommandPayloadBody::PaymentPayload(_) => Ok(Self(command)),
+            SignedCommandPayloadBody::StakeDelegation(_) => Err(CommandKindError {
+                expected: "payment",
+                command,
+            }),
+        }
+    }
+}
+
+impl From<PaymentCommand> for SignedCommand {
+    fn from(command: PaymentCommand) -> Self {
+        command.0
+    }
+}
+
+/// A signed command with a stake delegation body
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DelegationCommand(SignedCommand);
+
+impl DelegationCommand {
+    fn delegation(&self) -> (&CompressedPubKey, &CompressedPubKey) {
+        match &self.0.payload.body {
+            SignedCommandPayloadBody::StakeDelegation(StakeDelegation::SetDelegate {
+                delegator,
+                new_delegate,
+            }) => (delegator, new_delegate),
+            SignedCommandPayloadBody::PaymentPayload(_) => {
+                unreachable!("DelegationCommand is only built from stake delegations")
+            }
+        }
+    }
+
+    /// Account delegating its stake
+    pub fn delegator(&self) -> &CompressedPubKey {
+        self.delegation().0
+    }
+
+    /// Account the stake is delegated to
+    pub fn delegate(&self) -> &CompressedPubKey {
+        self.delegation().1
+    }
+
+    /// The underlying signed command
+    pub fn command(&self) -> &SignedCommand {
+        &self.0
+    }
+}
+
+impl TryFrom<SignedCommand> for DelegationCommand {
+    type Error = CommandKindError;
+
+    fn try_from(command: SignedCommand) -> Result<Self, Self::Error> {
+        match command.payload.body {
+            SignedCommandPayloadBody::StakeDelegation(_) => Ok(Self(command)),
+            SignedCommandPayloadBody::PaymentPayload(_) => Err(CommandKindError {
+                expected: "stake delegation",
+                command,
+            }),
+        }
+    }
+}
+
+impl From<DelegationCommand> for SignedCommand {
+    fn from(command: DelegationCommand) -> Self {
+        command.0
+    }
+}
+
+/// Error returned when converting a [SignedCommand] with a different kind of body,
+/// giving the command back
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("Expected a {expected} command")]
+pub struct CommandKindError {
+    /// The kind of command that was expected
+    pub expected: &'static str,
+    /// The command that could not be converted
+    pub command: SignedCommand,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user_commands::signed_command::builder::SignedDelegationCommandBuilder;
+    use crate::user_commands::signed_command::test_util::payment_test_1;
+    use proof_systems::mina_signer::{Keypair, NetworkId};
+
+    fn keys() -> (Keypair, CompressedPubKey) {
+        let kp =
+            Keypair::from_hex("164244176fddb5d769b7de2027469d027ad428fadcc0c02396e6280142efb718")
+                .expect("failed to create keypair");
+        let other = CompressedPubKey::from_address(
+            "B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt",
+        )
+        .unwrap();
+        (kp, other)
+    }
+
+    #[test]
+    fn payment_command_conversions() {
+        let (payload, kp) = payment_test_1();
+        let (_, receiver) = keys();
+        let cmd = SignedCommand::from_payload(payload, kp.clone(), NetworkId::TESTNET);
+
+        let payment = PaymentCommand::try_from(cmd.clone()).unwrap();
+        assert_eq!(payment.amount(), Amount(1729000000000));
+        assert_eq!(payment.receiver(), &receiver);
+        assert_eq!(payment.source(), &kp.public.into_compressed());
+        assert_eq!(payment.token_id(), &TokenId::default_token());
+        assert_eq!(payment.command(), &cmd);
+        assert_eq!(SignedCommand::from(payment), cmd);
+
+        let err = DelegationCommand::try_from(cmd.clone()).unwrap_err();
+        assert_eq!(err.expected, "stake delegation");
+        assert_eq!(err.command, cmd);
+    }
+
+    #[test]
+    fn delegation_command_conversions() {
+        let (kp, delegate) = keys();
+        let cmd = SignedDelegationCommandBuilder::new(
+            kp.public.into_compressed(),
+            delegate.clone(),
+            2000000000,
+            16,
+        )
+        .build()
+        .into_signed_command(kp.clone(), NetworkId::TESTNET);
+
+        let delegation = DelegationCommand::try_from(cmd.clone()).unwrap();
+        assert_eq!(delegation.delegate(), &delegate);
+        assert_eq!(delegation.delegator(), &kp.public.into_compressed());
+        assert_eq!(SignedCommand::from(delegation), cmd);
+
+        let err = PaymentCommand::try_from(cmd.clone()).unwrap_err();
+        assert_eq!(err.expected, "payment");
+        assert_eq!(err.command, cmd);
+    }
+}
@@ -3,10 +3,14 @@
 
 //! Traits and helpers for data structures that require validation
 
+use crate::public_key::CompressedPubKeyExt;
+use proof_systems::mina_hasher::Hashable;
+use proof_systems::mina_signer::{CompressedPubKey, Signature, Signer};
+
 /// Types that implement Verifiable are able to self-check using intrinsic data (e.g. signatures)
 /// and optionally some context that is required for peforming the verification
 /// e.g. a singleton signature verifier or param required for snark verification
-pub trait Verifiable<CTX> {
+pub trait Verifiable<CTX: ?Sized> {
     /// Accepts self and a context and
     /// returns if the implementor is valid
     ///
@@ -14,3 +18,100 @@ pub trait Verifiable<CTX> {
     /// shared signature checking context which performs self mutation as an optimization
     fn verify(&self, ctx: &mut CTX) -> bool;
 }
+
+/// Types carrying a payload together with the key that signed it and the signature.
+/// These are [Verifiable] with any signer context for their payload type without
+/// implementing the verification themselves
+pub trait Signable {
+    /// The part of the type that is hashed and signed
+    type Payload: Hashable;
+
+    /// The signed payload
+    fn payload(&self) -> &Self::Payload;
+
+    /// The public key of the signer
+    fn signer(&self) -> &CompressedPubKey;
+
+    /// The signature over the payload
+    fn signature(&self) -> &Signature;
+}
+
+impl<T, CTX> Verifiable<CTX> for T
+where
+    T: Signable,
+    CTX: Signer<T::Payload> + ?Sized,
+{
+    fn verify(&self, ctx: &mut CTX) -> bool {
+        match self.signer().to_pub_key() {
+            Ok(signer) => ctx.verify(self.signature(), &signer, self.payload()),
+            // A signer that is not a valid key cannot have signed anything
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proof_systems::mina_hasher::ROInput;
+    use proof_systems::mina_signer::{self, Keypair, NetworkId};
+
+    /// A payload that only commits to a nonce, standing in for other command types
+    #[derive(Clone)]
+    struct NoncePayload(u32);
+
+    impl Hashable for NoncePayload {
+        type D = NetworkId;
+
+        fn to_roinput(&self) -> ROInput {
+            ROInput::new().append_u32(self.0)
+        }
+
+        fn domain_string(_: NetworkId) -> Option<String> {
+            Some("CodaSignature".into())
+        }
+    }
+
+    struct SignedNonce {
+        payload: NoncePayload,
+        signer: CompressedPubKey,
+        signature: Signature,
+    }
+
+    impl Signable for SignedNonce {
+        type Payload = NoncePayload;
+
+        fn payload(&self) -> &NoncePayload {
+            &self.payload
+        }
+
+        fn signer(&self) -> &CompressedPubKey {
+            &self.signer
+        }
+
+        fn signature(&self) -> &Signature {
+            &self.signature
+        }
+    }
+
+    #[test]
+    fn signable_is_verifiable() {
+        let kp =
+            Keypair::from_hex("164244176fddb5d769b7de2027469d027ad428fadcc0c02396e6280142efb718")
+                .expect("failed to create keypair");
+        let mut ctx = mina_signer::create_legacy::<NoncePayload>(NetworkId::TESTNET);
+        let payload = NoncePayload(16);
+        let signed = SignedNonce {
+            signature: ctx.sign(&kp, &payload),
+            payload,
+            signer: kp.public.into_compressed(),
+        };
+        assert!(signed.verify(&mut ctx));
+
+        let tampered = SignedNonce {
+            payload: NoncePayload(17),
+            ..signed
+        };
+        assert!(!tampered.verify(&mut ctx));
+    }
+}
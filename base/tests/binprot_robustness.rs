@@ -0,0 +1,120 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Malformed gossip data must be rejected with an error rather than a panic
+
+use mina_rs_base::types::*;
+use mina_rs_base::user_commands::signed_command::builder::SignedTransferCommandBuilder;
+use mina_rs_base::BinProtSerializationType;
+use mina_serialization_types::{signatures::SignatureV1, v1::SignedCommandV1};
+use once_cell::sync::Lazy;
+use proof_systems::mina_signer::{CompressedPubKey, Keypair, NetworkId};
+use proptest::prelude::*;
+use proptest::sample::Index;
+use test_fixtures::TEST_BLOCKS;
+
+fn signed_command() -> SignedCommand {
+    let kp = Keypair::from_hex("164244176fddb5d769b7de2027469d027ad428fadcc0c02396e6280142efb718")
+        .unwrap();
+    let payload = SignedTransferCommandBuilder::new(
+        kp.public.into_compressed(),
+        CompressedPubKey::from_address("B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt")
+            .unwrap(),
+        1729000000000,
+        2000000000,
+        16,
+    )
+    .memo(SignedCommandMemo::try_from_text("Hello Mina!").unwrap())
+    .build();
+    SignedCommand::from_payload(payload, kp, NetworkId::MAINNET)
+}
+
+static SIGNED_COMMAND_BYTES: Lazy<Vec<u8>> =
+    Lazy::new(|| signed_command().try_into_binprot().unwrap());
+
+static PROTOCOL_STATE_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
+    let block = TEST_BLOCKS
+        .values()
+        .next()
+        .unwrap()
+        .external_transitionv1()
+        .unwrap();
+    let mut bytes = Vec::new();
+    bin_prot::to_writer(&mut bytes, &block.t.protocol_state).unwrap();
+    bytes
+});
+
+fn mutate(bytes: &[u8], mutations: &[(Index, u8)]) -> Vec<u8> {
+    let mut bytes = bytes.to_vec();
+    for (i, b) in mutations {
+        let i = i.index(bytes.len());
+        bytes[i] = *b;
+    }
+    bytes
+}
+
+#[test]
+fn binprot_fixtures_decode() {
+    assert_eq!(
+        SignedCommand::try_from_binprot(SIGNED_COMMAND_BYTES.as_slice()).unwrap(),
+        signed_command()
+    );
+    assert!(ProtocolStateLegacy::try_from_binprot(PROTOCOL_STATE_BYTES.as_slice()).is_ok());
+}
+
+proptest! {
+    #[test]
+    fn signed_command_from_random_bytes(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+        prop_assert!(SignedCommand::try_from_binprot(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn protocol_state_from_random_bytes(bytes in prop::collection::vec(any::<u8>(), 0..4096)) {
+        prop_assert!(ProtocolStateLegacy::try_from_binprot(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn signed_command_from_mutated_bytes(
+        mutations in prop::collection::vec((any::<Index>(), any::<u8>()), 1..8),
+    ) {
+        // Either outcome is fine as long as decoding returns
+        let _ = SignedCommand::try_from_binprot(mutate(&SIGNED_COMMAND_BYTES, &mutations).as_slice());
+    }
+
+    #[test]
+    fn protocol_state_from_mutated_bytes(
+        mutations in prop::collection::vec((any::<Index>(), any::<u8>()), 1..8),
+    ) {
+        let _ = ProtocolStateLegacy::try_from_binprot(
+            mutate(&PROTOCOL_STATE_BYTES, &mutations).as_slice(),
+        );
+    }
+
+    #[test]
+    fn signed_command_from_truncated_bytes(len in any::<Index>()) {
+        let bytes = &SIGNED_COMMAND_BYTES[..len.index(SIGNED_COMMAND_BYTES.len())];
+        prop_assert!(SignedCommand::try_from_binprot(bytes).is_err());
+    }
+
+    #[test]
+    fn protocol_state_from_truncated_bytes(len in any::<Index>()) {
+        let bytes = &PROTOCOL_STATE_BYTES[..len.index(PROTOCOL_STATE_BYTES.len())];
+        prop_assert!(ProtocolStateLegacy::try_from_binprot(bytes).is_err());
+    }
+}
+
+// Regression: field elements outside of their field used to panic when converting
+// the decoded signer or signature into the proof-systems types
+#[test]
+fn signed_command_non_canonical_field_elements() {
+    let mut signer: SignedCommandV1 = signed_command().into();
+    signer.t.t.signer.0.t.0.t.t.x = [0xff; 32];
+    let mut signature: SignedCommandV1 = signed_command().into();
+    signature.t.t.signature = SignatureV1(([0xff; 32], [0x00; 32]).into());
+
+    for v1 in [signer, signature] {
+        let mut bytes = Vec::new();
+        bin_prot::to_writer(&mut bytes, &v1).unwrap();
+        assert!(SignedCommand::try_from_binprot(bytes.as_slice()).is_err());
+    }
+}
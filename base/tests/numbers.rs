@@ -39,6 +39,31 @@ pub mod tests {
         assert_eq!(Amount::from_str("000000000").unwrap(), Amount(0));
     }
 
+    #[test]
+    fn test_amount_within_supply() {
+        assert_eq!(Amount::MAX_SUPPLY, Amount::from_str("1000000000").unwrap());
+        assert!(Amount(0).within_supply());
+        assert!(Amount::MAX_SUPPLY.within_supply());
+        assert!(!Amount(Amount::MAX_SUPPLY.0 + 1).within_supply());
+        assert!(!Amount(u64::MAX).within_supply());
+    }
+
+    #[test]
+    fn test_amount_checked_mul_u64() {
+        let amount = Amount(1_729_000_000_000);
+        assert_eq!(
+            amount.checked_mul_u64(1000),
+            Some(Amount(1_729_000_000_000_000))
+        );
+        assert_eq!(amount.checked_mul_u64(0), Some(Amount(0)));
+        assert_eq!(
+            Amount::MAX_SUPPLY.checked_mul_u64(18),
+            Some(Amount(18 * Amount::MAX_SUPPLY.0))
+        );
+        assert_eq!(Amount::MAX_SUPPLY.checked_mul_u64(19), None);
+        assert_eq!(Amount(u64::MAX).checked_mul_u64(2), None);
+    }
+
     #[test]
     fn test_convert_bigint_to_arkworks_zero() {
         use ark_ff::BigInteger256;
@@ -116,6 +141,84 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn numbers_order_by_inner_value() {
+        let mut fees = vec![Amount(2000000000), Amount(10100000), Amount(1000000000)];
+        fees.sort();
+        assert_eq!(
+            fees,
+            vec![Amount(10100000), Amount(1000000000), Amount(2000000000)]
+        );
+        assert!(Amount(10100000) < Amount(1000000000));
+        assert_eq!(fees.iter().max(), Some(&Amount(2000000000)));
+
+        assert!(AccountNonce(16) < AccountNonce(17));
+        assert!(GlobalSlotNumber(271828) < GlobalSlotNumber::MAX);
+        assert!(TokenId::default_token() < TokenId(2));
+    }
+
+    #[test]
+    fn global_slot_to_unix_timestamp() {
+        use proof_systems::mina_signer::NetworkId;
+
+        // Mainnet genesis at 2021-03-17 00:00 UTC, with 3 minute slots
+        assert_eq!(
+            GlobalSlotNumber(0).to_unix_timestamp(NetworkId::MAINNET),
+            1615939200
+        );
+        // 2022-10-04 07:24:00 UTC
+        assert_eq!(
+            GlobalSlotNumber(271828).to_unix_timestamp(NetworkId::MAINNET),
+            1664868240
+        );
+        assert_eq!(
+            GlobalSlotNumber::forever().to_unix_timestamp(NetworkId::MAINNET),
+            i64::MAX
+        );
+    }
+
+    #[test]
+    fn global_slot_from_unix_timestamp() {
+        use proof_systems::mina_signer::NetworkId;
+
+        let genesis = 1615939200;
+        assert_eq!(
+            GlobalSlotNumber::from_unix_timestamp(genesis, NetworkId::MAINNET),
+            GlobalSlotNumber(0)
+        );
+        // Before genesis
+        assert_eq!(
+            GlobalSlotNumber::from_unix_timestamp(genesis - 1, NetworkId::MAINNET),
+            GlobalSlotNumber(0)
+        );
+        assert_eq!(
+            GlobalSlotNumber::from_unix_timestamp(i64::MIN, NetworkId::MAINNET),
+            GlobalSlotNumber(0)
+        );
+        // 2022-10-04 07:24:00 UTC and the last second of that slot
+        for ts in [1664868240, 1664868240 + 179] {
+            assert_eq!(
+                GlobalSlotNumber::from_unix_timestamp(ts, NetworkId::MAINNET),
+                GlobalSlotNumber(271828)
+            );
+        }
+        assert_eq!(
+            GlobalSlotNumber::from_unix_timestamp(1664868240 + 180, NetworkId::MAINNET),
+            GlobalSlotNumber(271829)
+        );
+        assert!(!GlobalSlotNumber::from_unix_timestamp(i64::MAX, NetworkId::MAINNET).is_forever());
+    }
+
+    #[test]
+    fn checked_conversion_from_u64() {
+        let max = u32::MAX as u64;
+        assert_eq!(AccountNonce::try_from(max), Ok(AccountNonce(u32::MAX)));
+        assert!(AccountNonce::try_from(max + 1).is_err());
+        assert_eq!(GlobalSlotNumber::try_from(max), Ok(GlobalSlotNumber::MAX));
+        assert!(GlobalSlotNumber::try_from(max + 1).is_err());
+        assert_eq!(AccountNonce::try_from(16u64), Ok(AccountNonce(16)));
+    }
+
     #[macro_export]
     macro_rules! test_number_to_roinput {
         ($num:expr, $expected_hash_str:expr) => {
@@ -0,0 +1,136 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+use mina_rs_base::types::*;
+use mina_serialization_types::{json::*, v1::HashV1, version_bytes};
+use std::str::FromStr;
+use test_fixtures::*;
+
+const BLOCK_NAME: &str = "mainnet-113267-3NKtqqstB6h8SVNQCtspFisjUwCTqoQ6cC1KGvb6kx6n2dqKkiZS.json";
+const PREVIOUS_STATE_HASH: &str = "3NLC4u9241gWoCdEKohTAZpwCvDzAmjVACcRsfg4yN61Dgne3F17";
+
+#[test]
+fn protocol_state_json_roundtrip() -> anyhow::Result<()> {
+    let block_json = JSON_TEST_BLOCKS
+        .get(BLOCK_NAME)
+        .expect("Failed to load block");
+    let body_json: ProtocolStateBodyJson =
+        serde_json::from_value(block_json["protocol_state"]["body"].clone())?;
+    let protocol_state = ProtocolStateLegacy {
+        previous_state_hash: StateHash::from_str(PREVIOUS_STATE_HASH)?,
+        body: body_json.into(),
+    };
+
+    let json: ProtocolStateJson = protocol_state.clone().into();
+    let json_string = serde_json::to_string(&json)?;
+    let recovered_json: ProtocolStateJson = serde_json::from_str(&json_string)?;
+    assert_eq!(json, recovered_json);
+
+    let recovered: ProtocolStateLegacy = recovered_json.into();
+    assert_eq!(
+        recovered.previous_state_hash,
+        protocol_state.previous_state_hash
+    );
+    assert_eq!(recovered.body, protocol_state.body);
+    assert_eq!(recovered, protocol_state);
+    Ok(())
+}
+
+#[test]
+fn state_hash_json_is_base58check() -> anyhow::Result<()> {
+    let state_hash = StateHash::from_str(PREVIOUS_STATE_HASH)?;
+    let json: StateHashV1Json = state_hash.into();
+    assert_eq!(
+        serde_json::to_value(&json)?,
+        serde_json::Value::String(PREVIOUS_STATE_HASH.into())
+    );
+
+    // Must match the encoding the node emits in block json
+    let block_json = JSON_TEST_BLOCKS
+        .get(BLOCK_NAME)
+        .expect("Failed to load block");
+    let protocol_state_json: ProtocolStateJson =
+        serde_json::from_value(block_json["protocol_state"].clone())?;
+    assert_eq!(protocol_state_json.previous_state_hash, json);
+    assert_eq!(
+        serde_json::to_value(&protocol_state_json)?["previous_state_hash"],
+        block_json["protocol_state"]["previous_state_hash"]
+    );
+    Ok(())
+}
+
+#[test]
+fn state_hash_base58_roundtrip() -> anyhow::Result<()> {
+    let state_hash = StateHash::from_str(PREVIOUS_STATE_HASH)?;
+    assert_eq!(state_hash.to_string(), PREVIOUS_STATE_HASH);
+
+    // Version byte of state hashes, then the version of the hash and its 32 bytes
+    let decoded = bs58::decode(PREVIOUS_STATE_HASH)
+        .with_check(Some(version_bytes::STATE_HASH))
+        .into_vec()?;
+    assert_eq!(decoded.len(), 34);
+    let bytes: [u8; 32] = state_hash.clone().into();
+    assert_eq!(&decoded[2..], bytes.as_slice());
+
+    let hash_v1: HashV1 = state_hash.clone().into();
+    assert_eq!(StateHash::from(hash_v1), state_hash);
+    let json: StateHashV1Json = state_hash.clone().into();
+    assert_eq!(StateHash::from(json), state_hash);
+    Ok(())
+}
+
+#[test]
+fn protocol_state_from_json_state_hash() -> anyhow::Result<()> {
+    // The state hash of the block is the last part of its fixture name
+    const STATE_HASH: &str = "3NKtqqstB6h8SVNQCtspFisjUwCTqoQ6cC1KGvb6kx6n2dqKkiZS";
+    let block_json = JSON_TEST_BLOCKS
+        .get(BLOCK_NAME)
+        .expect("Failed to load block");
+    let json: ProtocolStateJson = serde_json::from_value(block_json["protocol_state"].clone())?;
+    let protocol_state: ProtocolStateLegacy = json.clone().into();
+
+    assert_eq!(protocol_state.state_hash().to_string(), STATE_HASH);
+    assert_eq!(
+        protocol_state.previous_state_hash.to_string(),
+        PREVIOUS_STATE_HASH
+    );
+    assert_eq!(ProtocolStateJson::from(protocol_state), json);
+    Ok(())
+}
+
+#[test]
+fn protocol_state_body_hash() -> anyhow::Result<()> {
+    use ark_ff::BigInteger256;
+    use num::BigUint;
+    use proof_systems::mina_hasher::{self, Fp, Hashable, Hasher, ROInput};
+
+    let block_json = JSON_TEST_BLOCKS
+        .get(BLOCK_NAME)
+        .expect("Failed to load block");
+    let json: ProtocolStateJson = serde_json::from_value(block_json["protocol_state"].clone())?;
+    let protocol_state: ProtocolStateLegacy = json.into();
+
+    let body_hash = protocol_state.body.body_hash();
+    assert_eq!(
+        BigUint::from(BigInteger256::from(body_hash)).to_str_radix(10),
+        "11547288559214200277520549031042137594317244691846831172842173442778999413309"
+    );
+
+    // The state hash combines the previous state hash with the body hash
+    struct Combined<'a>(&'a StateHash, Fp);
+    impl Hashable for Combined<'_> {
+        type D = ();
+
+        fn to_roinput(&self) -> ROInput {
+            ROInput::new().append_hashable(self.0).append_field(self.1)
+        }
+
+        fn domain_string(_: Self::D) -> Option<String> {
+            Some("CodaProtoState".into())
+        }
+    }
+    let combined = mina_hasher::create_legacy(())
+        .hash(&Combined(&protocol_state.previous_state_hash, body_hash));
+    assert_eq!(combined, protocol_state.state_hash_fp());
+    Ok(())
+}
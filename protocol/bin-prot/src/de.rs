@@ -9,9 +9,11 @@ use crate::value::layout::*;
 use crate::ReadBinProtExt;
 use crate::{caml_hash_variant, VariantHash};
 use byteorder::{LittleEndian, ReadBytesExt};
+use serde::de::DeserializeOwned;
 use serde::de::{self, value::U8Deserializer, EnumAccess, IntoDeserializer, Visitor};
 use serde::Deserialize;
-use std::io::{BufReader, Read};
+use std::io::{BufRead, BufReader, Read};
+use std::marker::PhantomData;
 
 /// the modes of operation for the deserializer
 pub struct StronglyTyped;
@@ -74,6 +76,46 @@ pub fn from_reader_strict<'de, R: Read, T: Deserialize<'de>>(rdr: R) -> Result<T
     }
 }
 
+/// Iterator that deserializes consecutive values of the same type from a single reader.
+/// Only the reader's internal buffer and the value being decoded are held in memory,
+/// so arbitrarily long streams (e.g. archive dumps of many blocks) can be replayed
+/// without loading them upfront.
+/// Iteration ends at the end of the stream, or after the first error since the
+/// position of the next value is unknown at that point.
+pub struct StreamDeserializer<R: Read, T> {
+    de: Deserializer<R, StronglyTyped>,
+    failed: bool,
+    output: PhantomData<T>,
+}
+
+impl<R: Read, T> StreamDeserializer<R, T> {
+    /// Create a stream deserializer from a reader
+    pub fn new(rdr: R) -> Self {
+        Self {
+            de: Deserializer::from_reader(rdr),
+            failed: false,
+            output: PhantomData,
+        }
+    }
+}
+
+impl<R: Read, T: DeserializeOwned> Iterator for StreamDeserializer<R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = match self.de.rdr.fill_buf() {
+            Ok(buf) if buf.is_empty() => return None,
+            Ok(_) => T::deserialize(&mut self.de),
+            Err(e) => Err(e.into()),
+        };
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
 // In the loosely typed case we want to use deserialize_any for every field
 // This includes the hybrid strong/loose case
 #[cfg(feature = "loose_deserialization")]
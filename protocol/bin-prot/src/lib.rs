@@ -19,10 +19,10 @@ pub mod value;
 mod write_ext;
 
 // pub use array::OcamlArray;
-pub use de::{from_reader, from_reader_strict, Deserializer};
+pub use de::{from_reader, from_reader_strict, Deserializer, StreamDeserializer};
 pub use polyvar::{caml_hash_variant, VariantHash};
 pub use read_ext::ReadBinProtExt;
-pub use ser::{to_writer, Serializer};
+pub use ser::{serialized_size, to_writer, Serializer};
 #[cfg(feature = "loose_deserialization")]
 pub use value::layout::{BinProtRule, Layout};
 pub use value::Value;
@@ -64,6 +64,30 @@ where
     value.serialize(&mut Serializer::new(writer))
 }
 
+/// Number of bytes the given value serializes to, counted without buffering the output
+pub fn serialized_size<T>(value: &T) -> Result<usize>
+where
+    T: Serialize,
+{
+    let mut counter = ByteCounter(0);
+    to_writer(&mut counter, value)?;
+    Ok(counter.0)
+}
+
+/// Writer that discards its input and only counts the bytes written
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 impl<'a, W> ser::Serializer for &'a mut Serializer<W>
 where
     W: std::io::Write,
@@ -91,3 +91,15 @@ fn roundtrip_enum() {
 fn roundtrip_owned_string() {
     common::roundtrip_test("serde-bin-prot".to_string());
 }
+
+#[test]
+fn serialized_size_matches_written_bytes() {
+    let mut bytes = Vec::new();
+    bin_prot::to_writer(&mut bytes, &PublicKey::new()).unwrap();
+    assert_eq!(
+        bin_prot::serialized_size(&PublicKey::new()).unwrap(),
+        bytes.len()
+    );
+    // version bytes, the x coordinate and the parity
+    assert_eq!(bytes.len(), 1 + 1 + 32 + 1);
+}
@@ -0,0 +1,30 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+use bin_prot::{error::Error, from_reader};
+
+// nat0 length prefix of u64::MAX
+const HUGE_LEN: [u8; 9] = [0xfc, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+
+#[test]
+fn string_with_oversized_length_is_rejected() {
+    let mut bytes = HUGE_LEN.to_vec();
+    bytes.extend_from_slice(b"abc");
+    let result: Result<String, _> = from_reader(bytes.as_slice());
+    assert!(matches!(result, Err(Error::Io(_))));
+}
+
+#[test]
+fn string_with_truncated_content_is_rejected() {
+    // Declares 4 bytes but only 3 follow
+    let result: Result<String, _> = from_reader([0x04, b'a', b'b', b'c'].as_slice());
+    assert!(matches!(result, Err(Error::Io(_))));
+}
+
+#[test]
+fn seq_with_oversized_length_is_rejected() {
+    let mut bytes = HUGE_LEN.to_vec();
+    bytes.extend_from_slice(&[0x01, 0x02]);
+    let result: Result<Vec<u8>, _> = from_reader(bytes.as_slice());
+    assert!(result.is_err());
+}
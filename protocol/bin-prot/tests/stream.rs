@@ -0,0 +1,56 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+use bin_prot::{to_writer, StreamDeserializer};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+struct Record {
+    id: u32,
+    name: String,
+    tags: Vec<u8>,
+}
+
+fn records(n: u32) -> Vec<Record> {
+    (0..n)
+        .map(|id| Record {
+            id,
+            name: format!("record-{}", id),
+            tags: vec![id as u8; (id % 7) as usize],
+        })
+        .collect()
+}
+
+#[test]
+fn stream_deserialize_consecutive_values() {
+    let expected = records(1000);
+    let mut bytes = Vec::new();
+    for r in &expected {
+        to_writer(&mut bytes, r).unwrap();
+    }
+
+    let decoded: Vec<Record> = StreamDeserializer::new(bytes.as_slice())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(decoded, expected);
+}
+
+#[test]
+fn stream_deserialize_empty() {
+    let mut stream = StreamDeserializer::<_, Record>::new(&[][..]);
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn stream_deserialize_stops_after_error() {
+    let mut bytes = Vec::new();
+    to_writer(&mut bytes, &records(2)[1]).unwrap();
+    // Truncated second value
+    let truncated = bytes[..3].to_vec();
+    bytes.extend(truncated);
+
+    let mut stream = StreamDeserializer::<_, Record>::new(bytes.as_slice());
+    assert!(matches!(stream.next(), Some(Ok(Record { id: 1, .. }))));
+    assert!(matches!(stream.next(), Some(Err(_))));
+    assert!(stream.next().is_none());
+}
@@ -16,6 +16,14 @@ pub enum Error {
     #[error("BinProtError: {0}")]
     BinProtError(#[from] bin_prot::error::Error),
 
+    /// Memo bytes are not in canonical form
+    #[error("Invalid memo: {0}")]
+    InvalidMemo(String),
+
+    /// Bytes are not the canonical encoding of an element of the expected field
+    #[error("Invalid field element: {0}")]
+    InvalidFieldElement(String),
+
     /// Custom error
     #[error("Custom error: {0}")]
     Custom(String),
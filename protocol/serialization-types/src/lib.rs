@@ -143,7 +143,7 @@ pub mod json {
         ProofStatePairingBasedJson, ProofStatementJson, ProtocolStateProofBase64Json,
         ProtocolStateProofJson, ShiftedValueJson, SpongeDigestBeforeEvaluations,
     };
-    pub use protocol_version::ProtocolVersionJson;
+    pub use protocol_version::{ProtocolVersionJson, ProtocolVersionStringJson};
     pub use signatures::{PublicKeyJson, SignatureJson};
     pub use snark_work::{
         FeeExcessJson, FeeExcessPairJson, OneORTwoJson, PendingCoinbaseJson,
@@ -3,7 +3,7 @@
 
 //! Types related to the Mina protocol state
 
-use crate::{json::*, v1::*};
+use crate::{json::*, v1::*, version_bytes};
 use mina_serialization_types_macros::AutoFrom;
 use serde::{Deserialize, Serialize};
 use versioned::*;
@@ -29,3 +29,204 @@ pub struct ProtocolStateJson {
     /// The body of the protocol state
     pub body: ProtocolStateBodyJson,
 }
+
+/// Error type for validating the json of a protocol state, see [ProtocolStateJson::validate]
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum SchemaError {
+    /// A required member is missing, or one of its parents is not an object
+    #[error("Missing member {0}")]
+    MissingMember(String),
+    /// A hash member is not a string
+    #[error("{0} is not a string")]
+    NotAString(String),
+    /// A hash member is not valid base58check
+    #[error("{path} is not valid base58check: {reason}")]
+    InvalidBase58 {
+        /// Dotted path of the member
+        path: String,
+        /// Why decoding failed
+        reason: String,
+    },
+    /// A hash member decodes to a different kind of hash
+    #[error("{path} has version byte {actual:#04x}, expected {expected:#04x}")]
+    WrongVersion {
+        /// Dotted path of the member
+        path: String,
+        /// Version byte of the expected kind of hash
+        expected: u8,
+        /// Version byte found
+        actual: u8,
+    },
+}
+
+// Base58check hash members of a protocol state, with the version byte of their kind of hash
+const HASH_MEMBERS: &[(&[&str], u8)] = &[
+    (&["previous_state_hash"], version_bytes::STATE_HASH),
+    (&["body", "genesis_state_hash"], version_bytes::STATE_HASH),
+    (
+        &[
+            "body",
+            "blockchain_state",
+            "staged_ledger_hash",
+            "non_snark",
+            "ledger_hash",
+        ],
+        version_bytes::LEDGER_HASH,
+    ),
+    (
+        &[
+            "body",
+            "blockchain_state",
+            "staged_ledger_hash",
+            "non_snark",
+            "aux_hash",
+        ],
+        version_bytes::STAGED_LEDGER_HASH_AUX_HASH,
+    ),
+    (
+        &[
+            "body",
+            "blockchain_state",
+            "staged_ledger_hash",
+            "non_snark",
+            "pending_coinbase_aux",
+        ],
+        version_bytes::STAGED_LEDGER_HASH_PENDING_COINBASE_AUX,
+    ),
+    (
+        &[
+            "body",
+            "blockchain_state",
+            "staged_ledger_hash",
+            "pending_coinbase_hash",
+        ],
+        version_bytes::COINBASE_HASH,
+    ),
+    (
+        &["body", "blockchain_state", "snarked_ledger_hash"],
+        version_bytes::LEDGER_HASH,
+    ),
+    (
+        &["body", "blockchain_state", "genesis_ledger_hash"],
+        version_bytes::LEDGER_HASH,
+    ),
+    (
+        &[
+            "body",
+            "consensus_state",
+            "staking_epoch_data",
+            "ledger",
+            "hash",
+        ],
+        version_bytes::LEDGER_HASH,
+    ),
+    (
+        &["body", "consensus_state", "staking_epoch_data", "seed"],
+        version_bytes::EPOCH_SEED,
+    ),
+    (
+        &[
+            "body",
+            "consensus_state",
+            "staking_epoch_data",
+            "start_checkpoint",
+        ],
+        version_bytes::STATE_HASH,
+    ),
+    (
+        &[
+            "body",
+            "consensus_state",
+            "staking_epoch_data",
+            "lock_checkpoint",
+        ],
+        version_bytes::STATE_HASH,
+    ),
+    (
+        &[
+            "body",
+            "consensus_state",
+            "next_epoch_data",
+            "ledger",
+            "hash",
+        ],
+        version_bytes::LEDGER_HASH,
+    ),
+    (
+        &["body", "consensus_state", "next_epoch_data", "seed"],
+        version_bytes::EPOCH_SEED,
+    ),
+    (
+        &[
+            "body",
+            "consensus_state",
+            "next_epoch_data",
+            "start_checkpoint",
+        ],
+        version_bytes::STATE_HASH,
+    ),
+    (
+        &[
+            "body",
+            "consensus_state",
+            "next_epoch_data",
+            "lock_checkpoint",
+        ],
+        version_bytes::STATE_HASH,
+    ),
+];
+
+// Other members a protocol state cannot be converted without, checked for presence only
+const REQUIRED_MEMBERS: &[&[&str]] = &[
+    &["body", "blockchain_state", "snarked_next_available_token"],
+    &["body", "blockchain_state", "timestamp"],
+    &["body", "consensus_state", "blockchain_length"],
+    &["body", "consensus_state", "curr_global_slot"],
+    &["body", "consensus_state", "global_slot_since_genesis"],
+    &["body", "consensus_state", "block_creator"],
+    &["body", "constants"],
+];
+
+fn member<'a>(
+    json: &'a serde_json::Value,
+    path: &[&str],
+) -> Result<&'a serde_json::Value, SchemaError> {
+    path.iter()
+        .try_fold(json, |value, name| value.as_object()?.get(*name))
+        .ok_or_else(|| SchemaError::MissingMember(path.join(".")))
+}
+
+impl ProtocolStateJson {
+    /// Checks the json of a protocol state from an untrusted source before deserializing it:
+    /// every hash member must be a base58check string with the version byte of its kind of
+    /// hash, and the nested members needed for the conversion into a protocol state must be
+    /// present. This takes the raw json as the typed form can only be built from valid hashes,
+    /// and reports the path of the first offending member
+    pub fn validate(json: &serde_json::Value) -> Result<(), SchemaError> {
+        for (path, expected) in HASH_MEMBERS {
+            let s = member(json, path)?
+                .as_str()
+                .ok_or_else(|| SchemaError::NotAString(path.join(".")))?;
+            let bytes = bs58::decode(s).with_check(None).into_vec().map_err(|e| {
+                SchemaError::InvalidBase58 {
+                    path: path.join("."),
+                    reason: e.to_string(),
+                }
+            })?;
+            match bytes.first() {
+                Some(actual) if actual == expected => {}
+                actual => {
+                    return Err(SchemaError::WrongVersion {
+                        path: path.join("."),
+                        expected: *expected,
+                        actual: actual.copied().unwrap_or_default(),
+                    })
+                }
+            }
+        }
+        for path in REQUIRED_MEMBERS {
+            member(json, path)?;
+        }
+        Ok(())
+    }
+}
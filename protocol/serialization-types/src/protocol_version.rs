@@ -3,7 +3,7 @@
 
 //! Protocol version structure
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use smart_default::SmartDefault;
 use versioned::Versioned;
 
@@ -32,3 +32,80 @@ impl From<ProtocolVersionV1> for ProtocolVersionJson {
         t.t
     }
 }
+
+/// ProtocolVersion in the `major.minor.patch` string form some node endpoints use.
+/// Deserializes from either the string or the `{ major, minor, patch }` object form,
+/// so versions can be ingested regardless of which one a node emits
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProtocolVersionStringJson {
+    /// Major version number
+    pub major: u32,
+    /// Minor version number
+    pub minor: u32,
+    /// Patch version number
+    pub patch: u32,
+}
+
+impl Serialize for ProtocolVersionStringJson {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("{}.{}.{}", self.major, self.minor, self.patch))
+    }
+}
+
+impl<'de> Deserialize<'de> for ProtocolVersionStringJson {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum AnyForm {
+            String(String),
+            Object(ProtocolVersion),
+        }
+
+        match AnyForm::deserialize(deserializer)? {
+            AnyForm::String(s) => {
+                let parts = s
+                    .split('.')
+                    .map(str::parse)
+                    .collect::<Result<Vec<u32>, _>>()
+                    .map_err(serde::de::Error::custom)?;
+                match parts.as_slice() {
+                    &[major, minor, patch] => Ok(Self {
+                        major,
+                        minor,
+                        patch,
+                    }),
+                    _ => Err(serde::de::Error::custom(format!(
+                        "Expected a version of the form major.minor.patch, got {s}"
+                    ))),
+                }
+            }
+            AnyForm::Object(v) => Ok(v.into()),
+        }
+    }
+}
+
+impl From<ProtocolVersion> for ProtocolVersionStringJson {
+    fn from(t: ProtocolVersion) -> Self {
+        Self {
+            major: t.major,
+            minor: t.minor,
+            patch: t.patch,
+        }
+    }
+}
+
+impl From<ProtocolVersionStringJson> for ProtocolVersion {
+    fn from(t: ProtocolVersionStringJson) -> Self {
+        Self {
+            major: t.major,
+            minor: t.minor,
+            patch: t.patch,
+        }
+    }
+}
@@ -8,12 +8,13 @@ use crate::{
     impl_strconv_via_json, version_bytes,
 };
 use mina_serialization_types_macros::AutoFrom;
-use proof_systems::mina_signer::CompressedPubKey;
+use proof_systems::mina_signer::{BaseField, CompressedPubKey, ScalarField};
+use proof_systems::o1_utils::field_helpers::FieldHelpers;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use versioned::Versioned;
 
 /// An EC point stored in compressed form containing only the x coordinate and one extra bit
-#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Serialize, Eq, PartialEq)]
 pub struct CompressedCurvePoint {
     /// The x coordinate of the EC point
     pub x: FieldElement,
@@ -21,6 +22,39 @@ pub struct CompressedCurvePoint {
     pub is_odd: bool,
 }
 
+impl CompressedCurvePoint {
+    /// Checks that x is a canonical base field element, so that the conversion into
+    /// [CompressedPubKey] cannot fail. This does not check that the point is on the curve
+    pub fn validate(&self) -> Result<(), crate::errors::Error> {
+        BaseField::from_bytes(&self.x).map(|_| ()).map_err(|_| {
+            crate::errors::Error::InvalidFieldElement(
+                "public key x coordinate is not a base field element".into(),
+            )
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for CompressedCurvePoint {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Same layout as the derived implementation, validated before it is handed out
+        #[derive(Deserialize)]
+        #[serde(rename = "CompressedCurvePoint")]
+        struct Unchecked {
+            x: FieldElement,
+            is_odd: bool,
+        }
+        let Unchecked { x, is_odd } = Unchecked::deserialize(deserializer)?;
+        let point = Self { x, is_odd };
+        point
+            .validate()
+            .map_err(<D::Error as serde::de::Error>::custom)?;
+        Ok(point)
+    }
+}
+
 /// An EC point stored in compressed form containing only the x coordinate and one extra bit (json)
 #[derive(Clone, Debug, Eq, PartialEq, AutoFrom)]
 #[auto_from(CompressedCurvePoint)]
@@ -69,9 +103,41 @@ pub struct PublicKeyV1(pub Versioned<Versioned<CompressedCurvePoint, 1>, 1>);
 pub struct PublicKey2V1(pub Versioned<PublicKeyV1, 1>); // with an extra version wrapper
 
 /// Signature (v1)
-#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Serialize, Eq, PartialEq)]
 pub struct SignatureV1(pub Versioned<Versioned<(FieldElement, InnerCurveScalar), 1>, 1>);
 
+/// Checks that rx is a canonical base field element and s a canonical scalar field element,
+/// so that the conversion into a proof-systems signature cannot fail
+fn validate_signature(rx: &FieldElement, s: &InnerCurveScalar) -> Result<(), crate::errors::Error> {
+    if BaseField::from_bytes(rx).is_err() {
+        return Err(crate::errors::Error::InvalidFieldElement(
+            "signature rx is not a base field element".into(),
+        ));
+    }
+    if ScalarField::from_bytes(s).is_err() {
+        return Err(crate::errors::Error::InvalidFieldElement(
+            "signature s is not a scalar field element".into(),
+        ));
+    }
+    Ok(())
+}
+
+impl<'de> Deserialize<'de> for SignatureV1 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Same layout as the derived implementation, validated before it is handed out
+        #[derive(Deserialize)]
+        #[serde(rename = "SignatureV1")]
+        struct Unchecked(Versioned<Versioned<(FieldElement, InnerCurveScalar), 1>, 1>);
+        let Unchecked(t) = Unchecked::deserialize(deserializer)?;
+        let (rx, s) = &t.t.t;
+        validate_signature(rx, s).map_err(<D::Error as serde::de::Error>::custom)?;
+        Ok(Self(t))
+    }
+}
+
 /// Signature (json)
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct SignatureJson(pub Versioned<(FieldElement, InnerCurveScalar), 1>);
@@ -100,22 +166,23 @@ impl<'de> Deserialize<'de> for SignatureJson {
             .with_check(Some(version_bytes::SIGNATURE))
             .into_vec()
             .map_err(<D::Error as serde::de::Error>::custom)?;
-        Ok(Self(
+        let t: Versioned<(FieldElement, InnerCurveScalar), 1> =
             bin_prot::from_reader_strict(&bytes[1..])
-                .map_err(<D::Error as serde::de::Error>::custom)?,
-        ))
+                .map_err(<D::Error as serde::de::Error>::custom)?;
+        validate_signature(&t.t.0, &t.t.1).map_err(<D::Error as serde::de::Error>::custom)?;
+        Ok(Self(t))
     }
 }
 
 mod conversions {
     use super::*;
-    use proof_systems::mina_signer::{BaseField, CompressedPubKey, ScalarField, Signature};
-    use proof_systems::o1_utils::field_helpers::FieldHelpers;
+    use proof_systems::mina_signer::Signature;
 
     impl From<&CompressedCurvePoint> for CompressedPubKey {
         fn from(t: &CompressedCurvePoint) -> Self {
             CompressedPubKey {
-                // This unwrap is safe as a PublicKeyV1 always has 32 bytes of data and from_bytes does not check if it is on curve
+                // This unwrap is safe as deserialization rejects x that are not base field elements
+                // and from_bytes does not check if it is on curve
                 x: BaseField::from_bytes(&t.x)
                     .expect("Wrong number of bytes encountered when converting to BaseField"),
                 is_odd: t.is_odd,
@@ -223,6 +290,7 @@ mod conversions {
     }
 
     fn internal_signature_to_signature(rx: &FieldElement, s: &InnerCurveScalar) -> Signature {
+        // These unwraps are safe as deserialization rejects components outside of their fields
         Signature {
             rx: BaseField::from_bytes(rx)
                 .expect("Wrong number of bytes encountered when converting to BaseField"),
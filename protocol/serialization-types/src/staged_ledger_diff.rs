@@ -236,9 +236,60 @@ impl_mina_enum_json_serde!(StakeDelegationJson, StakeDelegationJsonProxy);
 
 pub type SignedCommandFeeTokenV1 = Versioned<Versioned<Versioned<u64, 1>, 1>, 1>;
 
-#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Serialize, Eq, PartialEq)]
 pub struct SignedCommandMemo(pub Vec<u8>);
 
+impl SignedCommandMemo {
+    /// Checks that memo bytes are canonical, i.e. the declared length byte is at most 32 and
+    /// fits in the buffer, and every byte after the content is zero padding, so that no two
+    /// distinct byte strings decode to the same logical memo
+    pub fn validate_bytes(bytes: &[u8]) -> Result<(), crate::errors::Error> {
+        const LEN_INDEX: usize = 1;
+        const CONTENT_INDEX: usize = 2;
+        const MAX_CONTENT_LEN: usize = 32;
+        if bytes.len() < CONTENT_INDEX {
+            return Err(crate::errors::Error::InvalidMemo(format!(
+                "expected at least {} bytes, got {}",
+                CONTENT_INDEX,
+                bytes.len()
+            )));
+        }
+        let len = bytes[LEN_INDEX] as usize;
+        if len > MAX_CONTENT_LEN {
+            return Err(crate::errors::Error::InvalidMemo(format!(
+                "declared length {} exceeds the maximum of {}",
+                len, MAX_CONTENT_LEN
+            )));
+        }
+        let content_end = CONTENT_INDEX + len;
+        if content_end > bytes.len() {
+            return Err(crate::errors::Error::InvalidMemo(format!(
+                "declared length {} exceeds the {} available bytes",
+                len,
+                bytes.len() - CONTENT_INDEX
+            )));
+        }
+        if let Some(pos) = bytes[content_end..].iter().position(|&b| b != 0) {
+            return Err(crate::errors::Error::InvalidMemo(format!(
+                "non-zero padding byte at index {}",
+                content_end + pos
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for SignedCommandMemo {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        Self::validate_bytes(&bytes).map_err(<D::Error as serde::de::Error>::custom)?;
+        Ok(Self(bytes))
+    }
+}
+
 pub type SignedCommandMemoV1 = Versioned<SignedCommandMemo, 1>;
 
 #[derive(Clone, Debug, Eq, PartialEq, AutoFrom)]
@@ -268,7 +319,10 @@ impl<'de> Deserialize<'de> for SignedCommandMemoJson {
             .into_vec()
             .map_err(<D::Error as serde::de::Error>::custom)?;
         // Skip base58 check byte
-        Ok(Self(decoded.into_iter().skip(1).collect()))
+        let bytes: Vec<u8> = decoded.into_iter().skip(1).collect();
+        SignedCommandMemo::validate_bytes(&bytes)
+            .map_err(<D::Error as serde::de::Error>::custom)?;
+        Ok(Self(bytes))
     }
 }
 
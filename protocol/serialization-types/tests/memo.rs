@@ -0,0 +1,91 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+#[cfg(test)]
+mod tests {
+    use mina_serialization_types::{
+        json::SignedCommandMemoJson, staged_ledger_diff::SignedCommandMemo,
+    };
+
+    // Memo with text "FPayment"
+    const MEMO_BS58: &str = "E4Yd7qwaRCHR6t7i6ToM98eSUy5eKKadQUPZX7Vpw4CWBvWyd8fzK";
+    // Same memo with padding byte 20 set to 0x2a
+    const MEMO_TAMPERED_PADDING_BS58: &str =
+        "E4Yd7qwaRCHR6t7i6ToM98eSUy5eM4cqKczADoLSH2cQfegNVvCPW";
+    // Same memo with the length byte set to 33
+    const MEMO_LENGTH_OVERFLOW_BS58: &str = "E4ZTiTMNdzb41YVDd6Tqf7VTUKyViGqEUN4YHoba8U3tMcrYZsf5x";
+
+    fn memo_bytes() -> Vec<u8> {
+        let mut bytes = vec![0; 34];
+        bytes[0] = 1;
+        bytes[1] = 8;
+        bytes[2..10].copy_from_slice(b"FPayment");
+        bytes
+    }
+
+    fn binprot_decode(memo: &[u8]) -> Result<SignedCommandMemo, bin_prot::error::Error> {
+        let mut bytes = vec![memo.len() as u8];
+        bytes.extend_from_slice(memo);
+        bin_prot::from_reader_strict(bytes.as_slice())
+    }
+
+    #[test]
+    fn memo_canonical_binprot() {
+        let memo = binprot_decode(&memo_bytes()).unwrap();
+        assert_eq!(memo.0, memo_bytes());
+    }
+
+    #[test]
+    fn memo_tampered_padding_binprot() {
+        let mut bytes = memo_bytes();
+        bytes[20] = 0x2a;
+        assert!(binprot_decode(&bytes).is_err());
+        // The last byte is padding too
+        let mut bytes = memo_bytes();
+        bytes[33] = 1;
+        assert!(binprot_decode(&bytes).is_err());
+    }
+
+    #[test]
+    fn memo_length_overflow_binprot() {
+        let mut bytes = memo_bytes();
+        bytes[1] = 33;
+        assert!(binprot_decode(&bytes).is_err());
+        assert!(binprot_decode(&[1]).is_err());
+    }
+
+    #[test]
+    fn memo_declared_length_over_max_binprot() {
+        // Every length byte with a buffer large enough to hold the declared content, so only
+        // the 32 byte maximum can reject it
+        for len in 0..=u8::MAX {
+            let mut memo = vec![0; 2 + (len as usize).max(32)];
+            memo[0] = 1;
+            memo[1] = len;
+            memo[2..2 + len as usize].fill(b'a');
+            let mut bytes = Vec::new();
+            bin_prot::to_writer(&mut bytes, &SignedCommandMemo(memo)).unwrap();
+            let decoded: Result<SignedCommandMemo, _> =
+                bin_prot::from_reader_strict(bytes.as_slice());
+            assert_eq!(decoded.is_ok(), len <= 32, "length byte {len}");
+        }
+
+        let mut bytes = memo_bytes();
+        bytes[1] = 200;
+        assert!(binprot_decode(&bytes).is_err());
+    }
+
+    #[test]
+    fn memo_canonical_json() {
+        let memo: SignedCommandMemoJson =
+            serde_json::from_value(serde_json::json!(MEMO_BS58)).unwrap();
+        assert_eq!(memo.0, memo_bytes());
+    }
+
+    #[test]
+    fn memo_non_canonical_json() {
+        for s in [MEMO_TAMPERED_PADDING_BS58, MEMO_LENGTH_OVERFLOW_BS58] {
+            assert!(serde_json::from_value::<SignedCommandMemoJson>(serde_json::json!(s)).is_err());
+        }
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+#[cfg(test)]
+mod tests {
+    use mina_serialization_types::{
+        json::ProtocolStateJson, protocol_state::SchemaError, version_bytes,
+    };
+
+    fn protocol_state() -> serde_json::Value {
+        test_fixtures::JSON_TEST_BLOCKS
+            .get("mainnet-116121-3NK6myZRzc3GvS5iydv88on2XTEU2btYrjMVkgtbuoeXASRipSa6.json")
+            .unwrap()["protocol_state"]
+            .clone()
+    }
+
+    #[test]
+    fn protocol_state_json_validate_block() -> anyhow::Result<()> {
+        let json = protocol_state();
+        ProtocolStateJson::validate(&json)?;
+        let _: ProtocolStateJson = serde_json::from_value(json)?;
+        Ok(())
+    }
+
+    #[test]
+    fn protocol_state_json_validate_corrupted_hash() {
+        // Last character changed, so the checksum no longer matches
+        let mut json = protocol_state();
+        json["previous_state_hash"] = "3NLM1cC98jgBZxZDpmVJ6KxM4wkdEfJEhdYTHpKqXbd9mm97AVhz".into();
+        assert!(matches!(
+            ProtocolStateJson::validate(&json),
+            Err(SchemaError::InvalidBase58 { path, .. }) if path == "previous_state_hash"
+        ));
+        assert!(serde_json::from_value::<ProtocolStateJson>(json).is_err());
+
+        // A valid ledger hash where a state hash is expected
+        let mut json = protocol_state();
+        json["body"]["consensus_state"]["next_epoch_data"]["lock_checkpoint"] =
+            "jx7buQVWFLsXTtzRgSxbYcT8EYLS8KCZbLrfDcJxMtyy4thw2Ee".into();
+        assert_eq!(
+            ProtocolStateJson::validate(&json),
+            Err(SchemaError::WrongVersion {
+                path: "body.consensus_state.next_epoch_data.lock_checkpoint".into(),
+                expected: version_bytes::STATE_HASH,
+                actual: version_bytes::LEDGER_HASH,
+            })
+        );
+
+        let mut json = protocol_state();
+        json["body"]["genesis_state_hash"] = 42.into();
+        assert_eq!(
+            ProtocolStateJson::validate(&json),
+            Err(SchemaError::NotAString("body.genesis_state_hash".into()))
+        );
+    }
+
+    #[test]
+    fn protocol_state_json_validate_missing_member() {
+        let mut json = protocol_state();
+        json["body"].as_object_mut().unwrap().remove("constants");
+        assert_eq!(
+            ProtocolStateJson::validate(&json),
+            Err(SchemaError::MissingMember("body.constants".into()))
+        );
+
+        let mut json = protocol_state();
+        json["body"]["blockchain_state"] = serde_json::Value::Null;
+        assert_eq!(
+            ProtocolStateJson::validate(&json),
+            Err(SchemaError::MissingMember(
+                "body.blockchain_state.staged_ledger_hash.non_snark.ledger_hash".into()
+            ))
+        );
+    }
+}
@@ -3,6 +3,7 @@
 
 #[cfg(test)]
 mod tests {
+    use mina_serialization_types::signatures::{CompressedCurvePoint, SignatureV1};
     use proof_systems::mina_signer::CompressedPubKey;
 
     #[test]
@@ -23,4 +24,35 @@ mod tests {
         assert_eq!(pubkey, &pubkey_from_key);
         Ok(())
     }
+
+    #[test]
+    fn public_key_binprot_rejects_non_canonical_x() -> anyhow::Result<()> {
+        let pk = CompressedPubKey::from_address(
+            "B62qjCuPisQjLW7YkB22BR9KieSmUZTyApftqxsAuB3U21r3vj1YnaG",
+        )?;
+        let mut bytes = Vec::new();
+        bin_prot::to_writer(&mut bytes, &CompressedCurvePoint::from(&pk))?;
+        let point: CompressedCurvePoint = bin_prot::from_reader_strict(bytes.as_slice())?;
+        assert_eq!(CompressedPubKey::from(&point), pk);
+
+        // All ones exceeds the base field modulus
+        let point = CompressedCurvePoint {
+            x: [0xff; 32],
+            is_odd: false,
+        };
+        let mut bytes = Vec::new();
+        bin_prot::to_writer(&mut bytes, &point)?;
+        assert!(bin_prot::from_reader_strict::<_, CompressedCurvePoint>(bytes.as_slice()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn signature_binprot_rejects_non_canonical_components() -> anyhow::Result<()> {
+        for (rx, s) in [([0xff; 32], [0x00; 32]), ([0x00; 32], [0xff; 32])] {
+            let mut bytes = Vec::new();
+            bin_prot::to_writer(&mut bytes, &SignatureV1((rx, s).into()))?;
+            assert!(bin_prot::from_reader_strict::<_, SignatureV1>(bytes.as_slice()).is_err());
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,40 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mina_rs_base::types::*;
+use proof_systems::mina_signer::{CompressedPubKey, Keypair, PubKey};
+
+const KEYS: u16 = 10_000;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let keys: Vec<CompressedPubKey> = (1..=KEYS)
+        .map(|secret| {
+            Keypair::from_hex(&format!("{:064x}", secret))
+                .unwrap()
+                .public
+                .into_compressed()
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("decompress 10k public keys");
+    group.sample_size(10);
+    group.bench_function("direct decompression", |b| {
+        b.iter(|| {
+            for pk in &keys {
+                black_box(pk.to_pub_key().unwrap());
+            }
+        })
+    });
+    group.bench_function("address round-trip", |b| {
+        b.iter(|| {
+            for pk in &keys {
+                black_box(PubKey::from_address(&pk.into_address()).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,81 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mina_rs_base::types::*;
+use mina_rs_base::user_commands::signed_command::builder::SignedTransferCommandBuilder;
+use proof_systems::mina_signer::{self, CompressedPubKey, Keypair, NetworkId, Signer};
+
+const SIGNATURES: u32 = 10_000;
+const POOLED_SIGNATURES: u32 = 100_000;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let kp = Keypair::from_hex("164244176fddb5d769b7de2027469d027ad428fadcc0c02396e6280142efb718")
+        .unwrap();
+    let receiver =
+        CompressedPubKey::from_address("B62qicipYxyEHu7QjUqS7QvBipTs5CzgkYZZZkPoKVYBu6tnDUcE9Zt")
+            .unwrap();
+    let payments = |count: u32| -> Vec<SignedCommandPayload> {
+        (0..count)
+            .map(|nonce| {
+                SignedTransferCommandBuilder::new(
+                    kp.public.into_compressed(),
+                    receiver.clone(),
+                    1729000000000,
+                    2000000000,
+                    nonce,
+                )
+                .build()
+            })
+            .collect()
+    };
+    let payloads = payments(SIGNATURES);
+    let payloads_100k = payments(POOLED_SIGNATURES);
+
+    let mut group = c.benchmark_group("sign 10k payments");
+    group.sample_size(10);
+    group.bench_function("cached signer context", |b| {
+        b.iter(|| {
+            for payload in &payloads {
+                black_box(SignedCommand::from_payload(
+                    payload.clone(),
+                    kp.clone(),
+                    NetworkId::MAINNET,
+                ));
+            }
+        })
+    });
+    group.bench_function("signer context per command", |b| {
+        b.iter(|| {
+            for payload in &payloads {
+                let mut ctx =
+                    mina_signer::create_legacy::<SignedCommandPayload>(NetworkId::MAINNET);
+                black_box(ctx.sign(&kp, payload));
+            }
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("sign 100k payments");
+    group.sample_size(10);
+    group.bench_function("pooled signing context", |b| {
+        let mut ctx = SigningContext::new(NetworkId::MAINNET);
+        b.iter(|| {
+            for payload in &payloads_100k {
+                black_box(ctx.sign(payload.clone(), &kp));
+            }
+        })
+    });
+    group.bench_function("signer context per command", |b| {
+        b.iter(|| {
+            for payload in &payloads_100k {
+                let mut ctx = SigningContext::new(NetworkId::MAINNET);
+                black_box(ctx.sign(payload.clone(), &kp));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
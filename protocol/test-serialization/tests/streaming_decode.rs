@@ -0,0 +1,127 @@
+// Copyright 2020 ChainSafe Systems
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compares the peak heap usage of decoding a long stream of protocol states and
+//! staged ledger diffs straight from a reader against reading the whole stream into
+//! memory before decoding it.
+//! This lives in its own test binary since the counting allocator is global.
+
+use bin_prot::StreamDeserializer;
+use mina_serialization_types::v1::{ProtocolStateV1, StagedLedgerDiffV1};
+use serde::{de::DeserializeOwned, Serialize};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::Read;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use test_fixtures::TEST_BLOCKS;
+
+struct CountingAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Runs f and returns the peak heap usage on top of what was allocated beforehand
+fn peak_memory(f: impl FnOnce()) -> usize {
+    let baseline = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    f();
+    PEAK.load(Ordering::SeqCst) - baseline
+}
+
+/// Reader yielding the same encoded value `count` times without materializing the stream,
+/// standing in for a large archive dump on disk
+struct RepeatReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    remaining: usize,
+}
+
+impl<'a> RepeatReader<'a> {
+    fn new(bytes: &'a [u8], count: usize) -> Self {
+        Self {
+            bytes,
+            pos: 0,
+            remaining: count,
+        }
+    }
+}
+
+impl<'a> Read for RepeatReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+        let n = (self.bytes.len() - self.pos).min(buf.len());
+        buf[..n].copy_from_slice(&self.bytes[self.pos..self.pos + n]);
+        self.pos += n;
+        if self.pos == self.bytes.len() {
+            self.pos = 0;
+            self.remaining -= 1;
+        }
+        Ok(n)
+    }
+}
+
+const STREAM_LEN: usize = 200;
+
+fn compare_peak_memory<T: Serialize + DeserializeOwned>(value: &T) -> (usize, usize) {
+    let mut bytes = Vec::new();
+    bin_prot::to_writer(&mut bytes, value).unwrap();
+
+    let buffered = peak_memory(|| {
+        let mut all = Vec::new();
+        RepeatReader::new(&bytes, STREAM_LEN)
+            .read_to_end(&mut all)
+            .unwrap();
+        let mut count = 0;
+        for item in StreamDeserializer::<_, T>::new(all.as_slice()) {
+            item.unwrap();
+            count += 1;
+        }
+        assert_eq!(count, STREAM_LEN);
+    });
+    let streamed = peak_memory(|| {
+        let mut count = 0;
+        for item in StreamDeserializer::<_, T>::new(RepeatReader::new(&bytes, STREAM_LEN)) {
+            item.unwrap();
+            count += 1;
+        }
+        assert_eq!(count, STREAM_LEN);
+    });
+    (buffered, streamed)
+}
+
+#[test]
+fn streaming_decode_peak_memory() {
+    let block = TEST_BLOCKS
+        .values()
+        .next()
+        .unwrap()
+        .external_transitionv1()
+        .unwrap()
+        .t;
+
+    let (buffered, streamed) = compare_peak_memory::<ProtocolStateV1>(&block.protocol_state);
+    assert!(streamed < buffered);
+
+    let (buffered, streamed) = compare_peak_memory::<StagedLedgerDiffV1>(&block.staged_ledger_diff);
+    assert!(streamed < buffered);
+}
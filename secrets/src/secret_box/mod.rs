@@ -5,6 +5,11 @@
 //! This module contains utilties for loading keypair from wallet files
 //! that are generated from mina-keypair-gen tool
 //!
+//! Intermediate secret material (the password derived key, the decrypted
+//! private key bytes and their hex encoding) is held in [Zeroizing] buffers
+//! so that it is wiped from memory when dropped. The [Keypair] returned by
+//! [SecretBox::get_keypair] is not: its secret key is not wiped when dropped
+//!
 //! # Example
 //! ```rust
 //! use mina_secrets::secret_box::*;
@@ -29,3 +34,4 @@ use serde::{Deserialize, Serialize};
 
 // Re-export Keypair
 pub use proof_systems::mina_signer::Keypair;
+pub use zeroize::{Zeroize, Zeroizing};
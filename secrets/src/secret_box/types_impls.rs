@@ -83,7 +83,10 @@ impl TryInto<serde_json::Value> for SecretBox {
 }
 
 impl SecretBox {
-    pub(super) fn try_gen_secret(&self, password: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
+    pub(super) fn try_gen_secret(
+        &self,
+        password: impl AsRef<[u8]>,
+    ) -> Result<Zeroizing<Vec<u8>>, Error> {
         let hasher = (move || {
             let mut param_builder = ParamsBuilder::new();
             param_builder.m_cost((self.pw_mem_limit_bytes / 1024) as u32)?;
@@ -101,32 +104,40 @@ impl SecretBox {
             .map_err(|e: argon2::password_hash::Error| Error::PasswordHashError(format!("{}", e)))?
             .hash
         {
-            Ok((*hash.as_bytes()).into())
+            Ok(Zeroizing::new(hash.as_bytes().into()))
         } else {
             Err(Error::Argon2Error("Empty hash output".into()))
         }
     }
 
-    /// Gets raw bytes(little-endian) of the private key from the wallet with a password
-    pub fn get_private_key_bytes(&self, password: impl AsRef<[u8]>) -> Result<Vec<u8>, Error> {
+    /// Gets raw bytes(little-endian) of the private key from the wallet with a password,
+    /// the returned buffer is zeroed on drop
+    pub fn get_private_key_bytes(
+        &self,
+        password: impl AsRef<[u8]>,
+    ) -> Result<Zeroizing<Vec<u8>>, Error> {
         let secret = self.try_gen_secret(password)?;
         let key = GenericArray::from_slice(secret.as_slice());
         let cipher = XSalsa20Poly1305::new(key);
-        let mut bytes = cipher
-            .decrypt(
-                GenericArray::from_slice(self.nonce.as_slice()),
-                self.ciphertext.as_ref(),
-            )
-            .map_err(|e| Error::AeadError(format!("{}", e)))?;
+        let mut bytes = Zeroizing::new(
+            cipher
+                .decrypt(
+                    GenericArray::from_slice(self.nonce.as_slice()),
+                    self.ciphertext.as_ref(),
+                )
+                .map_err(|e| Error::AeadError(format!("{}", e)))?,
+        );
         bytes.remove(0);
         Ok(bytes)
     }
 
-    /// Gets [Keypair] from the wallet with a password
+    /// Gets [Keypair] from the wallet with a password,
+    /// unlike the intermediate buffers the returned keypair is not zeroed on drop
     pub fn get_keypair(&self, password: impl AsRef<[u8]>) -> Result<Keypair, Error> {
         let mut private_key_bytes = self.get_private_key_bytes(password)?;
         // mina scalars hex format is in big-endian order
         private_key_bytes.reverse();
-        Keypair::from_hex(&hex::encode(private_key_bytes)).map_err(Error::KeypairError)
+        let private_key_hex = Zeroizing::new(hex::encode(private_key_bytes.as_slice()));
+        Keypair::from_hex(&private_key_hex).map_err(Error::KeypairError)
     }
 }
@@ -47,6 +47,36 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[wasm_bindgen_test]
+    fn secret_box_private_key_bytes_zeroize() {
+        // Only compiles while the returned buffer is zeroed on drop
+        fn assert_zeroizing(_: &Zeroizing<Vec<u8>>) {}
+
+        let sb: SecretBox = PRIVATE_KEY_JSON.try_into().unwrap();
+        let private_key_bytes = sb.get_private_key_bytes(PASSWORD).unwrap();
+        assert_zeroizing(&private_key_bytes);
+        assert_eq!(private_key_bytes.len(), 32);
+        assert!(private_key_bytes.iter().any(|&b| b != 0));
+    }
+
+    #[test]
+    #[wasm_bindgen_test]
+    fn secret_box_private_key_bytes_zeroed() {
+        let sb: SecretBox = PRIVATE_KEY_JSON.try_into().unwrap();
+        let mut private_key_bytes = sb.get_private_key_bytes(PASSWORD).unwrap();
+        let len = private_key_bytes.len();
+        assert!(private_key_bytes.iter().any(|&b| b != 0));
+
+        // The wipe the wrapper runs on drop. It clears the vec but keeps the allocation,
+        // so the old contents can still be read back through the spare capacity
+        private_key_bytes.zeroize();
+        assert!(private_key_bytes.is_empty());
+        assert!(private_key_bytes.capacity() >= len);
+        let wiped = unsafe { std::slice::from_raw_parts(private_key_bytes.as_ptr(), len) };
+        assert!(wiped.iter().all(|&b| b == 0));
+    }
+
     #[wasm_bindgen_test]
     fn secret_box_keypair_wasm() {
         secret_box_keypair().unwrap();